@@ -11,25 +11,106 @@ impl Address {
         }
     }
 
+    pub fn new_reference(address: u64) -> Address {
+        Address {
+            data: AddressData::Reference(address),
+        }
+    }
+
     pub fn new_indexed(address: Address, index: Address) -> Address {
         Address {
-            data: AddressData::Indexed(address.as_u64(), index.as_u64()),
+            data: AddressData::Indexed(Box::from(address), Box::from(index)),
+        }
+    }
+
+    pub fn new_field(address: Address, field: &str) -> Address {
+        Address {
+            data: AddressData::Field(Box::from((
+                address,
+                Address::field_format(field),
+            ))),
         }
     }
 
     pub fn as_u64(&self) -> u64 {
-        match self.data {
-            AddressData::Simple(address) => address,
-            AddressData::Indexed(address, _index) => address,
+        match &self.data {
+            AddressData::Simple(address) => *address,
+            AddressData::Reference(address) => *address,
+            AddressData::Indexed(address, _index) => address.as_u64(),
+            AddressData::Field(field) => field.0.as_u64(),
         }
     }
 
+    // The array and the index of an indexed address.
+    pub fn indexing(&self) -> Option<(&Address, &Address)> {
+        match &self.data {
+            AddressData::Indexed(address, index) => Some((address, index)),
+            _ => None,
+        }
+    }
+
+    // Strings in variables, arrays and records have buffers of their own.
+    // The ones behind references may be shared or missing.
+    pub fn owns_buffer(&self) -> bool {
+        !matches!(self.data, AddressData::Reference(_))
+    }
+
     pub fn register_format(&self) -> String {
-        match self.data {
+        match &self.data {
             AddressData::Simple(address) => format!("r{}", address),
+            AddressData::Reference(address) => format!("(*r{})", address),
             AddressData::Indexed(address, index) => {
-                format!("r{}[r{}]", address, index)
+                format!("{}[{}]", address, index)
             }
+            AddressData::Field(field) => format!("{}.{}", field.0, field.1),
+        }
+    }
+
+    // The declarator mirrors the usage, so references are declared as
+    // pointers.
+    pub fn declaration_format(&self) -> String {
+        match &self.data {
+            AddressData::Reference(address) => format!("*r{}", address),
+            _ => format!("r{}", self.as_u64()),
+        }
+    }
+
+    // Fields get a prefix so that they never clash with C keywords.
+    pub fn field_format(field: &str) -> String {
+        format!("f_{}", field)
+    }
+
+    pub fn size_format(&self) -> String {
+        self.dimension_size_format(0)
+    }
+
+    // An array parameter also gets the line of the call, "r1_line", for the
+    // checks made on its argument.
+    pub fn line_format(&self) -> String {
+        format!("r{}_line", self.as_u64())
+    }
+
+    // Every dimension of an array has a size of its own, "r1_size" is the
+    // length of "r1" and "r1_size1" the length of every "r1[i]". The sizes
+    // of an array field are fields next to it, "r1.f_a_size".
+    pub fn dimension_size_format(&self, dimension: usize) -> String {
+        match &self.data {
+            AddressData::Indexed(address, _index) => {
+                address.dimension_size_format(dimension + 1)
+            }
+            AddressData::Field(field) => format!(
+                "{}.{}",
+                field.0,
+                Address::size_name(&field.1, dimension)
+            ),
+            _ => Address::size_name(&format!("r{}", self.as_u64()), dimension),
+        }
+    }
+
+    pub fn size_name(name: &str, dimension: usize) -> String {
+        match dimension {
+            0 => format!("{}_size", name),
+            _ => format!("{}_size{}", name, dimension),
         }
     }
 }
@@ -43,5 +124,7 @@ impl fmt::Display for Address {
 #[derive(PartialEq, Clone, Debug)]
 pub enum AddressData {
     Simple(u64),
-    Indexed(u64, u64),
+    Reference(u64),
+    Indexed(Box<Address>, Box<Address>),
+    Field(Box<(Address, String)>),
 }
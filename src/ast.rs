@@ -1,45 +1,100 @@
 use crate::token::Token;
 
 pub enum AST {
-    Program(Token, Vec<Subroutine>, Vec<Statement>),
+    Program(Token, Vec<Statement>, Vec<Subroutine>, Vec<Statement>),
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ParameterMode {
+    Value,
+    Var,
 }
 
 pub enum Subroutine {
     Function(
         Token,
-        Vec<(Token, TypeDescription)>,
+        Vec<(Token, TypeDescription, ParameterMode)>,
         Vec<Statement>,
         TypeDescription,
     ),
-    Procedure(Token, Vec<(Token, TypeDescription)>, Vec<Statement>),
+    Procedure(
+        Token,
+        Vec<(Token, TypeDescription, ParameterMode)>,
+        Vec<Statement>,
+    ),
+    ForwardFunction(
+        Token,
+        Vec<(Token, TypeDescription, ParameterMode)>,
+        TypeDescription,
+    ),
+    ForwardProcedure(Token, Vec<(Token, TypeDescription, ParameterMode)>),
 }
 
 pub enum Statement {
     Assign(Box<Variable>, Expression),
     Declaration(Token, TypeDescription),
+    Constant(Token, Expression),
+    TypeDeclaration(Token, TypeDescription),
     Block(Vec<Statement>),
     If(Expression, Box<Statement>, Option<Box<Statement>>),
     While(Expression, Box<Statement>),
+    For(Token, Expression, Token, Expression, Box<Statement>),
+    Repeat(Vec<Statement>, Expression),
+    Case(
+        Expression,
+        Vec<(Vec<CaseLabel>, Statement)>,
+        Option<Box<Statement>>,
+    ),
     Assert(Token, Expression),
     Call(Token, Vec<Expression>),
     Return(Token, Option<Expression>),
+    Subroutines(Vec<Subroutine>),
+}
+
+pub enum CaseLabel {
+    Single(Expression),
+    Range(Expression, Expression),
 }
 
+#[derive(Clone)]
 pub enum Expression {
     Literal(Token),
     Variable(Box<Variable>),
     Binary(Box<Expression>, Box<Expression>, Token),
     Unary(Box<Expression>, Token),
     Call(Token, Vec<Expression>),
+    // "f(x)[i]" indexes the array a call returns, the token names the
+    // function.
+    Index(Box<Expression>, Token, Vec<Expression>),
+    // "x:8" and "r:10:3" as arguments of write and writeln, the token is the
+    // first colon.
+    Formatted(
+        Box<Expression>,
+        Token,
+        Box<Expression>,
+        Option<Box<Expression>>,
+    ),
 }
 
+#[derive(Clone)]
 pub enum TypeDescription {
     Simple(Token),
-    Array(Token, Expression),
+    // The element type and the size.
+    Array(Box<TypeDescription>, Expression),
+    // An array parameter of any length, "array of T".
+    OpenArray(Token, Box<TypeDescription>),
+    Record(Token, Vec<(Token, TypeDescription)>),
+    Enumeration(Token, Vec<Token>),
+    Subrange(Token, Expression, Expression),
 }
 
+#[derive(Clone)]
 pub enum Variable {
     Simple(Token),
-    Indexed(Token, Expression),
+    // "a[i][j]" and "a[i, j]" both have the indices i and j.
+    Indexed(Token, Vec<Expression>),
+    Field(Box<Variable>, Token),
+    // The indices of a selected field, "r.a[i]".
+    FieldIndexed(Box<Variable>, Vec<Expression>),
 }
 
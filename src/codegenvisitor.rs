@@ -1,6 +1,10 @@
 use crate::address::Address;
+use crate::ast::ParameterMode;
 use crate::opkind::*;
+use crate::options::Options;
+use crate::options::StringOverflow;
 use crate::token::Token;
+use crate::token::TokenKind;
 use crate::typedast::*;
 use crate::visitor::TypedVisitor;
 use std::fs::File;
@@ -13,10 +17,16 @@ pub struct CodeGenVisitor {
     _free_buffer: String,
     label_no: u32,
     max_string_size: u64,
+    options: Options,
+    // The array type the function being generated returns.
+    result_type: Option<NodeType>,
+    // The copies of the string value parameters of the subroutine being
+    // generated, freed when it returns.
+    string_copies: Vec<Address>,
 }
 
 impl CodeGenVisitor {
-    pub fn new() -> CodeGenVisitor {
+    pub fn new(options: Options) -> CodeGenVisitor {
         return CodeGenVisitor {
             buffer: String::new(),
             declaration_buffer: String::new(),
@@ -24,6 +34,9 @@ impl CodeGenVisitor {
             _free_buffer: String::new(),
             label_no: 0,
             max_string_size: 512,
+            options,
+            result_type: None,
+            string_copies: Vec::new(),
         };
     }
 
@@ -54,25 +67,140 @@ impl CodeGenVisitor {
             SimpleType::Boolean => String::from("short"),
             SimpleType::Real => String::from("double"),
             SimpleType::String => String::from("char *"),
+            SimpleType::Char => String::from("char"),
         }
     }
 
     fn type_conversion_from_node_type(source_type: &NodeType) -> String {
         match source_type {
             NodeType::Simple(t) => CodeGenVisitor::type_conversion(t),
-            NodeType::ArrayOf(t) => {
-                format!("{}*", CodeGenVisitor::type_conversion(t))
+            NodeType::ArrayOf(t, _) => {
+                format!(
+                    "{}*",
+                    CodeGenVisitor::type_conversion_from_node_type(t)
+                )
             }
+            NodeType::Record(number, _fields) => {
+                format!("struct record{}", number)
+            }
+            NodeType::Enumeration(..) | NodeType::Subrange(..) => {
+                String::from("int")
+            }
+        }
+    }
+
+    fn dimensions(node_type: &NodeType) -> usize {
+        match node_type {
+            NodeType::ArrayOf(element, _) => {
+                1 + CodeGenVisitor::dimensions(element)
+            }
+            _ => 0,
         }
     }
 
+    fn innermost_element_type(node_type: &NodeType) -> &NodeType {
+        match node_type {
+            NodeType::ArrayOf(element, _) => {
+                CodeGenVisitor::innermost_element_type(element)
+            }
+            _ => node_type,
+        }
+    }
+
+    // Strings, arrays and the records holding them need their buffers set
+    // up when they are created.
+    fn has_buffers(node_type: &NodeType) -> bool {
+        match node_type {
+            NodeType::Simple(SimpleType::String) | NodeType::ArrayOf(..) => {
+                true
+            }
+            NodeType::Record(_number, fields) => {
+                fields.iter().any(|(_name, field_type, _sizes)| {
+                    CodeGenVisitor::has_buffers(field_type)
+                })
+            }
+            _ => false,
+        }
+    }
+
+    // The runtime gives the elements of an array their buffers with this.
+    fn element_init(node_type: &NodeType) -> String {
+        let element = CodeGenVisitor::innermost_element_type(node_type);
+        match element {
+            NodeType::Simple(SimpleType::String) => {
+                String::from("mp_string_init")
+            }
+            NodeType::Record(number, _fields)
+                if CodeGenVisitor::has_buffers(element) =>
+            {
+                CodeGenVisitor::record_function(*number, "init")
+            }
+            _ => String::from("NULL"),
+        }
+    }
+
+    // The runtime copies the elements of an array with this, the strings
+    // into the buffers of the target.
+    fn element_copy(node_type: &NodeType) -> String {
+        let element = CodeGenVisitor::innermost_element_type(node_type);
+        match element {
+            NodeType::Simple(SimpleType::String) => {
+                String::from("mp_string_element_copy")
+            }
+            NodeType::Record(number, _fields)
+                if CodeGenVisitor::has_buffers(element) =>
+            {
+                CodeGenVisitor::record_function(*number, "copy")
+            }
+            _ => String::from("NULL"),
+        }
+    }
+
+    fn record_function(number: u64, function: &str) -> String {
+        format!("record{}_{}", number, function)
+    }
+
+    // The sizes of the dimensions as a C array for the runtime.
+    fn size_list(address: &Address, dimensions: usize) -> String {
+        let sizes: Vec<String> = (0..dimensions)
+            .map(|dimension| address.dimension_size_format(dimension))
+            .collect();
+        format!("(int[]){{{}}}", sizes.join(", "))
+    }
+
+    // A function returning an array writes the sizes of the result through
+    // these hidden parameters.
+    fn result_size_format(dimension: usize) -> String {
+        match dimension {
+            0 => String::from("result_size"),
+            _ => format!("result_size{}", dimension),
+        }
+    }
+
+    // Adds items to the end of a parenthesized list.
+    fn extend_list(list: String, items: &Vec<String>) -> String {
+        let inner = &list[1..list.len() - 1];
+        if inner.trim().is_empty() {
+            format!("({})", items.join(", "))
+        } else {
+            format!("({}, {})", inner, items.join(", "))
+        }
+    }
+
+    fn enumeration_names(number: u64) -> String {
+        format!("enumeration{}_names", number)
+    }
+
     fn printf_format_conversion(source_type: NodeType) -> String {
         match source_type {
             NodeType::Simple(t) => match t {
                 SimpleType::Integer | SimpleType::Boolean => String::from("%d"),
                 SimpleType::Real => String::from("%f"),
                 SimpleType::String => String::from("%s"),
+                SimpleType::Char => String::from("%c"),
             },
+            NodeType::Enumeration(..) => String::from("%s"),
+            NodeType::Subrange(..) => String::from("%d"),
             _ => String::from("Error array printing not implemented"),
         }
     }
@@ -94,9 +222,18 @@ impl CodeGenVisitor {
                     format!("{} {}", t, item_id)
                 }
             }
-            NodeType::ArrayOf(t) => {
-                format!("{} *{}", CodeGenVisitor::type_conversion(&t), item_id)
-            }
+            NodeType::ArrayOf(t, _) => format!(
+                "{} *{}",
+                CodeGenVisitor::type_conversion_from_node_type(&t),
+                item_id
+            ),
+            NodeType::Record(..)
+            | NodeType::Enumeration(..)
+            | NodeType::Subrange(..) => format!(
+                "{} {}",
+                CodeGenVisitor::type_conversion_from_node_type(&source_type),
+                item_id
+            ),
         }
     }
 
@@ -156,25 +293,16 @@ impl CodeGenVisitor {
         rhs_addr: &Address,
         result_addr: &Address,
         op: &OpKind,
+        token: &Token,
     ) {
         let text = match op {
-            OpKind::Addition => {
-                let alloc_text = format!(
-                    "{} = (char *) malloc({});\n",
-                    result_addr.clone(),
-                    self.max_string_size,
-                );
-                let text = alloc_text
-                    + format!(
-                        "strcpy({},{});\nstrcat({},{});\n",
-                        result_addr.clone(),
-                        lhs_addr,
-                        result_addr,
-                        rhs_addr,
-                    )
-                    .as_str();
-                Some(text)
-            }
+            OpKind::Addition => Some(format!(
+                "{} = mp_string_concat({}, {}, {});\n",
+                result_addr,
+                lhs_addr,
+                rhs_addr,
+                token.row + 1
+            )),
             OpKind::Relational(variant) => match variant {
                 Relational::Equal => {
                     let text = format!(
@@ -287,9 +415,32 @@ impl CodeGenVisitor {
         self.add_declaration(line3);
     }
 
-    fn visit_size(&mut self, array_address: &Address, address: &Address) {
+    fn visit_runtime_call(
+        &mut self,
+        node: &TypedExpression,
+        function: &str,
+        arguments: &Vec<TypedExpression>,
+    ) {
+        for arg in arguments {
+            self.visit_expression(arg);
+        }
+        self.declare(&node.address, &node.node_type);
+        let addresses: Vec<String> = arguments
+            .iter()
+            .map(|arg| arg.address.to_string())
+            .collect();
+        self.add_code(format!(
+            "{} = {}({});\n",
+            node.address,
+            function,
+            addresses.join(", ")
+        ));
+    }
+
+    fn visit_size(&mut self, array: &TypedExpression, address: &Address) {
+        self.visit_expression(array);
         let declaration_text = format!("int {};\n", address);
-        let text = format!("{} = {}_size;\n", address, array_address);
+        let text = format!("{} = {};\n", address, array.address.size_format());
         self.add_declaration(declaration_text);
         self.add_code(text);
     }
@@ -297,8 +448,46 @@ impl CodeGenVisitor {
     fn visit_variable(&mut self, var: &TypedVariable) {
         match &var.substructure {
             TypedVariableStructure::Simple => (),
-            TypedVariableStructure::Indexed(expr) => {
-                self.visit_expression(expr)
+            TypedVariableStructure::Indexed(indices) => {
+                for index in indices {
+                    self.visit_expression(index);
+                }
+                if self.options.bounds_checks {
+                    self.check_bounds(&var.address, &var.token);
+                }
+            }
+            TypedVariableStructure::Field(record) => {
+                self.visit_variable(record)
+            }
+            TypedVariableStructure::Element(array, index) => {
+                self.visit_variable(array);
+                self.visit_expression(index);
+                if self.options.bounds_checks {
+                    self.add_code(format!(
+                        "mp_bounds_check({}, {}, \"{}\", {}, {});\n",
+                        index.address,
+                        array.address.size_format(),
+                        var.token.lexeme,
+                        var.token.row + 1,
+                        var.token.column + 1
+                    ));
+                }
+            }
+            TypedVariableStructure::Character(string, index, offset) => {
+                self.visit_variable(string);
+                self.visit_expression(index);
+                if self.options.bounds_checks {
+                    self.add_code(format!(
+                        "mp_char_check({}, {}, \"{}\", {}, {});\n",
+                        index.address,
+                        string.address,
+                        var.token.lexeme,
+                        var.token.row + 1,
+                        var.token.column + 1
+                    ));
+                }
+                self.add_declaration(format!("int {};\n", offset));
+                self.add_code(format!("{} = {} - 1;\n", offset, index.address));
             }
         }
     }
@@ -309,12 +498,21 @@ impl CodeGenVisitor {
         op: &OpKind,
         result_addr: &Address,
         out_type: &NodeType,
+        token: &Token,
     ) {
         self.visit_expression(lhs);
         self.visit_expression(rhs);
         self.declare(&result_addr, out_type);
         match &lhs.node_type {
-            NodeType::ArrayOf(_t) => (), // Arrays not supported on Binary expression
+            // Arrays and records not supported on Binary expression
+            NodeType::ArrayOf(..) | NodeType::Record(..) => (),
+            NodeType::Enumeration(..) | NodeType::Subrange(..) => self
+                .numeric_expression(
+                    &lhs.address,
+                    &rhs.address,
+                    result_addr,
+                    op,
+                ),
             NodeType::Simple(t) => match t {
                 SimpleType::Boolean => self.boolean_expression(
                     &lhs.address,
@@ -322,18 +520,20 @@ impl CodeGenVisitor {
                     result_addr,
                     op,
                 ),
-                SimpleType::Integer | SimpleType::Real => self
-                    .numeric_expression(
+                SimpleType::Integer | SimpleType::Real | SimpleType::Char => {
+                    self.numeric_expression(
                         &lhs.address,
                         &rhs.address,
                         result_addr,
                         op,
-                    ),
+                    )
+                }
                 SimpleType::String => self.string_expression(
                     &lhs.address,
                     &rhs.address,
                     result_addr,
                     op,
+                    token,
                 ),
             },
         }
@@ -350,61 +550,273 @@ impl CodeGenVisitor {
             self.visit_expression(arg);
         }
         self.declare(out_address, out_type);
-        let args_text = self.args_call_format(arguments);
+        let mut args_text = self.args_call_format(arguments);
+        if let NodeType::ArrayOf(..) = out_type {
+            let sizes: Vec<String> = (0..CodeGenVisitor::dimensions(out_type))
+                .map(|dimension| out_address.dimension_size_format(dimension))
+                .collect();
+            for size in &sizes {
+                self.add_declaration(format!("int {};\n", size));
+            }
+            let references = sizes.iter().map(|size| format!("&{}", size));
+            args_text =
+                CodeGenVisitor::extend_list(args_text, &references.collect());
+        }
         let text = format!("{} = {}{};\n", out_address, address, args_text);
         self.add_code(text);
     }
 
+    // Each index is checked against the size of the dimension it indexes,
+    // outermost first.
+    fn check_bounds(&mut self, address: &Address, token: &Token) {
+        let mut checks = Vec::new();
+        let mut address = address;
+        while let Some((array, index)) = address.indexing() {
+            checks.push(format!(
+                "mp_bounds_check({}, {}, \"{}\", {}, {});\n",
+                index,
+                array.size_format(),
+                token.lexeme,
+                token.row + 1,
+                token.column + 1
+            ));
+            address = array;
+        }
+        for check in checks.into_iter().rev() {
+            self.add_code(check);
+        }
+    }
+
     fn assign_simple(
         &mut self,
         variable: &TypedVariable,
         value: &TypedExpression,
     ) {
-        match &variable.substructure {
-            TypedVariableStructure::Indexed(index_expr) => {
-                self.visit_expression(index_expr);
+        self.visit_variable(variable);
+        let text = match &variable.node_type {
+            NodeType::Simple(SimpleType::String)
+                if variable.address.owns_buffer() =>
+            {
+                format!(
+                    "{} = mp_string_assign({}, {}, {});\n",
+                    &variable.address,
+                    &variable.address,
+                    &value.address,
+                    variable.token.row + 1
+                )
             }
-            _ => (),
+            NodeType::Simple(SimpleType::String) => format!(
+                "{} = mp_string_copy({}, {});\n",
+                &variable.address,
+                &value.address,
+                variable.token.row + 1
+            ),
+            _ => format!("{} = {};\n", &variable.address, &value.address),
         };
-        let text = format!("{} = {};\n", &variable.address, &value.address);
         self.add_code(text);
     }
 
+    fn assign_record(
+        &mut self,
+        variable: &TypedVariable,
+        value: &TypedExpression,
+    ) {
+        self.visit_variable(variable);
+        if let NodeType::Record(number, _fields) = &variable.node_type {
+            self.add_code(format!(
+                "{}(&{}, &{}, {});\n",
+                CodeGenVisitor::record_function(*number, "copy"),
+                variable.address,
+                value.address,
+                variable.token.row + 1
+            ));
+        }
+    }
+
     fn assign_array(
         &mut self,
         variable: &TypedVariable,
         value: &TypedExpression,
-        st: SimpleType,
     ) {
-        let type_text = CodeGenVisitor::type_conversion(&st);
+        self.visit_variable(variable);
+        let dimensions = CodeGenVisitor::dimensions(&variable.node_type);
+        let element_type =
+            CodeGenVisitor::innermost_element_type(&variable.node_type);
+        let type_text =
+            CodeGenVisitor::type_conversion_from_node_type(element_type);
         let text = format!(
-            "memcpy({}, {}, {}_size * sizeof({}));\n",
-            variable.address, value.address, variable.address, type_text
+            "copy_array({}, {}, {}, {}, {}, sizeof({}), {}, {});\n",
+            variable.address,
+            value.address,
+            CodeGenVisitor::size_list(&variable.address, dimensions),
+            CodeGenVisitor::size_list(&value.address, dimensions),
+            dimensions,
+            type_text,
+            CodeGenVisitor::element_copy(&variable.node_type),
+            variable.token.row + 1
         );
         self.add_code(text);
     }
 
-    fn visit_write(&mut self, arguments: &Vec<TypedExpression>) {
-        let mut node_types = Vec::new();
-        let mut adresses = Vec::new();
+    // Only writeln separates its arguments with a space, and only when none
+    // has a field width, so formatted output is written exactly as asked. The
+    // width and the precision are passed to printf with "*". Arrays are
+    // written as lists of their elements.
+    fn visit_write(
+        &mut self,
+        arguments: &Vec<TypedWriteArgument>,
+        newline: bool,
+    ) {
+        let separated =
+            newline && arguments.iter().all(|arg| arg.width.is_none());
+        let separator = if separated { " " } else { "" };
         for arg in arguments {
-            self.visit_expression(arg);
-            node_types.push(arg.node_type.clone());
-            adresses.push(&arg.address);
+            self.visit_expression(&arg.value);
+            let mut star = "";
+            let mut field = Vec::new();
+            if let Some(width) = &arg.width {
+                self.visit_expression(width);
+                field.push(width.address.to_string());
+                star = "*";
+            }
+            if let Some(precision) = &arg.precision {
+                self.visit_expression(precision);
+                field.push(precision.address.to_string());
+                star = "*.*";
+            }
+            let code = match &arg.value.node_type {
+                NodeType::ArrayOf(..) => format!(
+                    "{}{}",
+                    match separated {
+                        true => format!("printf(\"{}\");\n", separator),
+                        false => String::new(),
+                    },
+                    CodeGenVisitor::write_array(
+                        &arg.value.node_type,
+                        arg.value.address.to_string(),
+                        &arg.value.address,
+                        0,
+                        star,
+                        &field
+                    )
+                ),
+                node_type => CodeGenVisitor::write_value(
+                    node_type,
+                    arg.value.address.to_string(),
+                    separator,
+                    star,
+                    &field,
+                ),
+            };
+            self.add_code(code);
+        }
+        if newline {
+            self.add_code(String::from("printf(\"\\n\");\n"));
+        }
+    }
+
+    // Booleans are printed by the runtime, enumeration members by their
+    // names.
+    fn write_value(
+        node_type: &NodeType,
+        value: String,
+        separator: &str,
+        star: &str,
+        field: &[String],
+    ) -> String {
+        let (conversion, value) = match node_type {
+            NodeType::Simple(SimpleType::Boolean) => {
+                (String::from("%s"), format!("mp_boolean_name({})", value))
+            }
+            NodeType::Enumeration(number, _members) => (
+                String::from("%s"),
+                format!(
+                    "{}[{}]",
+                    CodeGenVisitor::enumeration_names(*number),
+                    value
+                ),
+            ),
+            _ => (
+                CodeGenVisitor::printf_format_conversion(node_type.clone()),
+                value,
+            ),
+        };
+        let arguments: String = field
+            .iter()
+            .chain(std::iter::once(&value))
+            .map(|argument| format!(", {}", argument))
+            .collect();
+        format!(
+            "printf(\"{}%{}{}\"{});\n",
+            separator,
+            star,
+            &conversion[1..],
+            arguments
+        )
+    }
+
+    // Every dimension is a loop over its size, "[1, 2]" for the elements.
+    fn write_array(
+        node_type: &NodeType,
+        element: String,
+        address: &Address,
+        dimension: usize,
+        star: &str,
+        field: &[String],
+    ) -> String {
+        match node_type {
+            NodeType::ArrayOf(element_type, _) => {
+                let index = format!("i{}", dimension);
+                let inner = CodeGenVisitor::write_array(
+                    element_type,
+                    format!("{}[{}]", element, index),
+                    address,
+                    dimension + 1,
+                    star,
+                    field,
+                );
+                format!(
+                    "printf(\"[\");\nfor (int {i} = 0; {i} < {size}; {i}++) {{\n\
+                     if ({i} > 0) printf(\", \");\n{inner}}}\nprintf(\"]\");\n",
+                    i = index,
+                    size = address.dimension_size_format(dimension),
+                    inner = inner
+                )
+            }
+            _ => {
+                CodeGenVisitor::write_value(node_type, element, "", star, field)
+            }
         }
-        let format_text = self.c_format_for_node_types(&node_types);
-        let arg_text = self.c_write_address_formats(&adresses);
-        let text = format!("printf({}, {});", format_text, arg_text);
-        let text2 = String::from("printf(\"\\n\");\n");
-        self.add_code(text);
-        self.add_code(text2);
     }
 
     fn args_call_format(&mut self, arguments: &Vec<TypedExpression>) -> String {
         let mut text = String::from("(");
         for i in 0..arguments.len() {
             if let Some(arg) = arguments.get(i) {
-                text = format!("{} {}", text, arg.address);
+                text = match (&arg.substructure, &arg.node_type) {
+                    (
+                        TypedExpressionStructure::Reference(_var),
+                        NodeType::Simple(_)
+                        | NodeType::Record(..)
+                        | NodeType::Enumeration(..)
+                        | NodeType::Subrange(..),
+                    ) => format!("{} &{}", text, arg.address),
+                    (_, NodeType::ArrayOf(..)) => {
+                        let dimensions =
+                            CodeGenVisitor::dimensions(&arg.node_type);
+                        let sizes: Vec<String> = (0..dimensions)
+                            .map(|d| arg.address.dimension_size_format(d))
+                            .collect();
+                        format!(
+                            "{} {}, {}, {}",
+                            text,
+                            arg.address,
+                            sizes.join(", "),
+                            arg.token.row + 1
+                        )
+                    }
+                    _ => format!("{} {}", text, arg.address),
+                };
             }
             if i < arguments.len() - 1 {
                 text = format!("{},", text);
@@ -413,14 +825,44 @@ impl CodeGenVisitor {
         format!("{})", text)
     }
 
+    // Strings are read by the runtime, which knows how long they can be.
     fn visit_read(&mut self, variables: &Vec<Box<TypedVariable>>) {
         for var in variables {
             self.visit_variable(var);
+            let text = match &var.node_type {
+                NodeType::Simple(SimpleType::String) => format!(
+                    "{} = mp_string_read({}, {});\n",
+                    var.address,
+                    if var.address.owns_buffer() {
+                        var.address.to_string()
+                    } else {
+                        String::from("NULL")
+                    },
+                    var.token.row + 1
+                ),
+                _ => {
+                    let variable = vec![var.clone()];
+                    format!(
+                        "scanf({}, {});\n",
+                        self.read_scanf_formats(&variable),
+                        self.read_address_formats(&variable)
+                    )
+                }
+            };
+            self.add_code(text);
+            // Read values are checked like assigned ones.
+            if let NodeType::Subrange(low, high) = &var.node_type {
+                if self.options.range_checks {
+                    self.add_code(format!(
+                        "mp_range_check({}, {}, {}, {});\n",
+                        var.address,
+                        low,
+                        high,
+                        var.token.row + 1
+                    ));
+                }
+            }
         }
-        let formats = self.read_scanf_formats(variables);
-        let adressess = self.read_address_formats(variables);
-        let text = format!("scanf({}, {});\n", formats, adressess);
-        self.add_code(text);
     }
 
     fn read_scanf_formats(
@@ -471,44 +913,112 @@ impl CodeGenVisitor {
         text
     }
 
-    fn c_write_address_formats(&mut self, adressess: &Vec<&Address>) -> String {
-        let mut text = String::new();
-        for i in 0..adressess.len() {
-            if let Some(arg) = adressess.get(i) {
-                text = format!("{} {}", text, arg);
-            }
-            if i < adressess.len() - 1 {
-                text = format!("{},", text);
+    fn visit_unary(
+        &mut self,
+        main_node: &TypedExpression,
+        rhs: &TypedExpression,
+    ) {
+        self.visit_expression(rhs);
+        self.declare(&main_node.address, &main_node.node_type);
+        let operator = match main_node.token.token_kind {
+            TokenKind::Minus => "-",
+            TokenKind::Plus => "+",
+            _ => "!",
+        };
+        let text =
+            format!("{} = {}{};\n", main_node.address, operator, rhs.address);
+        self.add_code(text);
+    }
+
+    // An enumeration can not step past its first or last member.
+    fn visit_step(
+        &mut self,
+        main_node: &TypedExpression,
+        ordinal: &TypedExpression,
+        step: i32,
+    ) {
+        self.visit_expression(ordinal);
+        self.declare(&main_node.address, &main_node.node_type);
+        let text = format!(
+            "{} = {} + {};\n",
+            main_node.address, ordinal.address, step
+        );
+        self.add_code(text);
+        if let NodeType::Enumeration(_number, members) = &main_node.node_type {
+            let text = format!(
+                "mp_range_check({}, 0, {}, {});\n",
+                main_node.address,
+                members.len() - 1,
+                main_node.token.row + 1
+            );
+            self.add_code(text);
+        }
+    }
+
+    fn visit_range_check(
+        &mut self,
+        main_node: &TypedExpression,
+        value: &TypedExpression,
+    ) {
+        self.visit_expression(value);
+        if let NodeType::Subrange(low, high) = &main_node.node_type {
+            if self.options.range_checks {
+                let text = format!(
+                    "mp_range_check({}, {}, {}, {});\n",
+                    main_node.address,
+                    low,
+                    high,
+                    main_node.token.row + 1
+                );
+                self.add_code(text);
             }
         }
-        text
     }
 
-    fn visit_unary(
+    fn visit_conversion(
         &mut self,
         main_node: &TypedExpression,
-        rhs: &TypedExpression,
+        source: &TypedExpression,
     ) {
-        self.visit_expression(rhs);
+        self.visit_expression(source);
+        // Only chr converts to a char.
+        if main_node.node_type == NodeType::Simple(SimpleType::Char)
+            && self.options.range_checks
+        {
+            self.add_code(format!(
+                "mp_range_check({}, 0, 255, {});\n",
+                source.address,
+                main_node.token.row + 1
+            ));
+        }
         self.declare(&main_node.address, &main_node.node_type);
-        let text = format!("{} = !{};\n", main_node.address, rhs.address);
+        let type_text = CodeGenVisitor::type_conversion_from_node_type(
+            &main_node.node_type,
+        );
+        let text = format!(
+            "{} = ({}) {};\n",
+            main_node.address, type_text, source.address
+        );
         self.add_code(text);
     }
 
     fn visit_procedure(
         &mut self,
         address: &Address,
-        parameters: &Vec<(TypedVariable, TypedTypeDescription)>,
+        parameters: &Vec<(TypedVariable, TypedTypeDescription, ParameterMode)>,
         block: &Vec<TypedStatement>,
     ) {
-        let param_string = self.build_param_string(parameters);
-        let definition = format!("void {}{} {{\n", address, param_string);
+        let heading = self.build_heading(address, parameters, None);
+        let definition = format!("{} {{\n", heading);
         self.declaration_buffer.push_str(definition.as_str());
+        self.result_type = None;
+        self.string_copies = Vec::new();
         for param in parameters {
-            let (var, type_description) = param;
-            self.visit_parameter(var, type_description);
+            let (var, type_description, mode) = param;
+            self.visit_parameter(var, type_description, mode);
         }
         self.visit_block(block);
+        self.free_string_copies(None);
         self.add_code(format!("return;\n}}\n"));
         self.ready_buffer.push_str(self.declaration_buffer.as_str());
         self.ready_buffer.push_str(self.buffer.as_str());
@@ -520,62 +1030,128 @@ impl CodeGenVisitor {
         &mut self,
         var: &TypedVariable,
         type_description: &TypedTypeDescription,
+        mode: &ParameterMode,
     ) {
-        match type_description {
-            TypedTypeDescription::Array(_node_type, expression) => {
-                self.visit_expression(&expression);
-                self.declaration_buffer.push_str(
-                    format!(
-                        "int {}_size = {};\n",
-                        var.address,
-                        expression.address.clone()
-                    )
-                    .as_str(),
-                );
+        // The sizes of the argument are passed in, an array with size
+        // expressions checks them against its own.
+        if let TypedTypeDescription::Array(_node_type, sizes) = type_description
+        {
+            for (dimension, size) in sizes.iter().enumerate() {
+                self.visit_expression(size);
+                self.add_code(format!(
+                    "mp_length_check({}, {}, {});\n",
+                    var.address.dimension_size_format(dimension),
+                    size.address,
+                    var.address.line_format()
+                ));
             }
-            TypedTypeDescription::Simple(_node_type) => (),
+        }
+        if let (ParameterMode::Value, NodeType::ArrayOf(..)) =
+            (mode, &var.node_type)
+        {
+            let dimensions = CodeGenVisitor::dimensions(&var.node_type);
+            let element_type =
+                CodeGenVisitor::innermost_element_type(&var.node_type);
+            let sizes = CodeGenVisitor::size_list(&var.address, dimensions);
+            self.add_code(format!(
+                "{} = ({}) clone_array({}, {}, {}, {}, sizeof({}), {}, {}, {});\n",
+                var.address,
+                CodeGenVisitor::type_conversion_from_node_type(&var.node_type),
+                var.address,
+                sizes,
+                sizes,
+                dimensions,
+                CodeGenVisitor::type_conversion_from_node_type(element_type),
+                CodeGenVisitor::element_init(&var.node_type),
+                CodeGenVisitor::element_copy(&var.node_type),
+                var.address.line_format()
+            ));
         }
         match &var.node_type {
             NodeType::Simple(simple_type) => match simple_type {
-                SimpleType::String => {
-                    self.declaration_buffer.push_str(
-                        format!(
-                            "int {}_size = {};\n",
-                            var.address, self.max_string_size
-                        )
-                        .as_str(),
+                SimpleType::String => self.declaration_buffer.push_str(
+                    format!(
+                        "int {} = {};\n",
+                        var.address.size_format(),
+                        self.max_string_size
                     )
-                }
+                    .as_str(),
+                ),
                 _ => (),
             },
             _ => (),
         }
+        // The record has the buffers of the argument, it gets its own.
+        if let (ParameterMode::Value, NodeType::Record(number, _fields)) =
+            (mode, &var.node_type)
+        {
+            if CodeGenVisitor::has_buffers(&var.node_type) {
+                self.add_code(format!(
+                    "{{\n{} argument = {a};\n{}(&{a});\n{}(&{a}, &argument, {});\n}}\n",
+                    CodeGenVisitor::type_conversion_from_node_type(
+                        &var.node_type
+                    ),
+                    CodeGenVisitor::record_function(*number, "init"),
+                    CodeGenVisitor::record_function(*number, "copy"),
+                    var.token.row + 1,
+                    a = var.address
+                ));
+            }
+        }
+        if let (ParameterMode::Value, NodeType::Simple(SimpleType::String)) =
+            (mode, &var.node_type)
+        {
+            self.add_code(format!(
+                "{} = mp_string_copy({}, {});\n",
+                var.address,
+                var.address,
+                var.token.row + 1
+            ));
+            self.string_copies.push(var.address.clone());
+        }
+    }
+
+    // A copy that is returned belongs to the caller.
+    fn free_string_copies(&mut self, returned: Option<&Address>) {
+        let copies: Vec<String> = self
+            .string_copies
+            .iter()
+            .filter(|copy| Some(*copy) != returned)
+            .map(|copy| format!("free({});\n", copy))
+            .collect();
+        self.add_code(copies.concat());
     }
 
     fn visit_function(
         &mut self,
         address: &Address,
-        parameters: &Vec<(TypedVariable, TypedTypeDescription)>,
+        parameters: &Vec<(TypedVariable, TypedTypeDescription, ParameterMode)>,
         block: &Vec<TypedStatement>,
         out_type: &TypedTypeDescription,
     ) {
-        let out_type_string = match out_type {
-            TypedTypeDescription::Simple(node_type) => {
-                CodeGenVisitor::type_conversion_from_node_type(node_type)
-            }
-            TypedTypeDescription::Array(node_type, _expr) => {
-                CodeGenVisitor::type_conversion_from_node_type(node_type)
-            }
-        };
-        let param_string = self.build_param_string(parameters);
-        let definition =
-            format!("{} {}{} {{\n", out_type_string, address, param_string);
+        let heading = self.build_heading(address, parameters, Some(out_type));
+        let definition = format!("{} {{\n", heading);
         self.declaration_buffer.push_str(definition.as_str());
+        self.result_type = None;
+        self.string_copies = Vec::new();
         for param in parameters {
-            let (var, type_description) = param;
-            self.visit_parameter(var, type_description);
+            let (var, type_description, mode) = param;
+            self.visit_parameter(var, type_description, mode);
+        }
+        // The sizes of the result are fixed when the function is entered.
+        if let TypedTypeDescription::Array(node_type, sizes) = out_type {
+            for (dimension, size) in sizes.iter().enumerate() {
+                self.visit_expression(size);
+                self.add_code(format!(
+                    "*{} = {};\n",
+                    CodeGenVisitor::result_size_format(dimension),
+                    size.address
+                ));
+            }
+            self.result_type = Some(node_type.clone());
         }
         self.visit_block(block);
+        self.free_string_copies(None);
         self.add_code(format!("\n}}\n"));
         self.ready_buffer.push_str(self.declaration_buffer.as_str());
         self.ready_buffer.push_str(self.buffer.as_str());
@@ -585,16 +1161,39 @@ impl CodeGenVisitor {
 
     fn build_param_string(
         &mut self,
-        parameters: &Vec<(TypedVariable, TypedTypeDescription)>,
+        parameters: &Vec<(TypedVariable, TypedTypeDescription, ParameterMode)>,
     ) -> String {
         let mut text = String::from("(");
         for i in 0..parameters.len() {
             if let Some(param) = parameters.get(i) {
-                let (variable, _type_def) = param;
+                let (variable, _type_def, _mode) = param;
                 let type_text = CodeGenVisitor::type_conversion_from_node_type(
                     &variable.node_type,
                 );
-                text = format!("{}{} {}", text, type_text, variable.address);
+                text = format!(
+                    "{}{} {}",
+                    text,
+                    type_text,
+                    variable.address.declaration_format()
+                );
+                // Arrays get the sizes of their arguments and the line of the
+                // call passed in.
+                if let NodeType::ArrayOf(..) = &variable.node_type {
+                    let dimensions =
+                        CodeGenVisitor::dimensions(&variable.node_type);
+                    for dimension in 0..dimensions {
+                        text = format!(
+                            "{}, int {}",
+                            text,
+                            variable.address.dimension_size_format(dimension)
+                        );
+                    }
+                    text = format!(
+                        "{}, int {}",
+                        text,
+                        variable.address.line_format()
+                    );
+                }
             }
             if i < parameters.len() - 1 {
                 text = format!("{},", text);
@@ -602,14 +1201,253 @@ impl CodeGenVisitor {
         }
         return format!("{})", text);
     }
+
+    fn build_heading(
+        &mut self,
+        address: &Address,
+        parameters: &Vec<(TypedVariable, TypedTypeDescription, ParameterMode)>,
+        out_type: Option<&TypedTypeDescription>,
+    ) -> String {
+        let out_type_string = match out_type {
+            Some(TypedTypeDescription::Simple(node_type)) => {
+                CodeGenVisitor::type_conversion_from_node_type(node_type)
+            }
+            Some(TypedTypeDescription::Array(node_type, _expr)) => {
+                CodeGenVisitor::type_conversion_from_node_type(node_type)
+            }
+            None => String::from("void"),
+        };
+        let mut param_string = self.build_param_string(parameters);
+        if let Some(TypedTypeDescription::Array(node_type, _)) = out_type {
+            let sizes = (0..CodeGenVisitor::dimensions(node_type)).map(|d| {
+                format!("int* {}", CodeGenVisitor::result_size_format(d))
+            });
+            param_string =
+                CodeGenVisitor::extend_list(param_string, &sizes.collect());
+        }
+        format!("{} {}{}", out_type_string, address, param_string)
+    }
+
+    // The records come in the order they were declared in, so the records
+    // a record contains are defined before it.
+    fn build_struct(record: &NodeType) -> String {
+        let mut text = format!(
+            "{} {{\n",
+            CodeGenVisitor::type_conversion_from_node_type(record)
+        );
+        if let NodeType::Record(_number, fields) = record {
+            for (name, field_type, sizes) in fields {
+                let field = Address::field_format(name);
+                text = format!(
+                    "{}{} {};\n",
+                    text,
+                    CodeGenVisitor::type_conversion_from_node_type(field_type),
+                    field
+                );
+                for dimension in 0..sizes.len() {
+                    text = format!(
+                        "{}int {};\n",
+                        text,
+                        Address::size_name(&field, dimension)
+                    );
+                }
+            }
+        }
+        format!("{}}};\n", text)
+    }
+
+    // Sets up the buffers of every string the record holds, the records in
+    // it included.
+    fn build_record_init(record: &NodeType) -> String {
+        let mut text = String::new();
+        if let NodeType::Record(number, fields) = record {
+            text = format!(
+                "void {}(void* element) {{\n{} *record = element;\n",
+                CodeGenVisitor::record_function(*number, "init"),
+                CodeGenVisitor::type_conversion_from_node_type(record)
+            );
+            for (name, field_type, sizes) in fields {
+                let field = format!("record->{}", Address::field_format(name));
+                text = match field_type {
+                    NodeType::ArrayOf(..) => {
+                        let mut size_list = Vec::new();
+                        for (dimension, size) in sizes.iter().enumerate() {
+                            let size_field =
+                                Address::size_name(&field, dimension);
+                            text =
+                                format!("{}{} = {};\n", text, size_field, size);
+                            size_list.push(size_field);
+                        }
+                        format!(
+                            "{}{} = ({}) alloc_array((int[]){{{}}}, {}, sizeof({}), {});\n",
+                            text,
+                            field,
+                            CodeGenVisitor::type_conversion_from_node_type(
+                                field_type
+                            ),
+                            size_list.join(", "),
+                            sizes.len(),
+                            CodeGenVisitor::type_conversion_from_node_type(
+                                CodeGenVisitor::innermost_element_type(
+                                    field_type
+                                )
+                            ),
+                            CodeGenVisitor::element_init(field_type)
+                        )
+                    }
+                    NodeType::Simple(SimpleType::String) => format!(
+                        "{}{} = mp_string_copy(\"\", 0);\n",
+                        text, field
+                    ),
+                    NodeType::Record(number, _fields)
+                        if CodeGenVisitor::has_buffers(field_type) =>
+                    {
+                        format!(
+                            "{}{}(&{});\n",
+                            text,
+                            CodeGenVisitor::record_function(*number, "init"),
+                            field
+                        )
+                    }
+                    _ => text,
+                };
+            }
+        }
+        format!("{}}}\n", text)
+    }
+
+    // Copies the fields one by one, the strings into the buffers of the
+    // target, so that the copies share nothing.
+    fn build_record_copy(record: &NodeType) -> String {
+        let mut text = String::new();
+        if let NodeType::Record(number, fields) = record {
+            let type_text =
+                CodeGenVisitor::type_conversion_from_node_type(record);
+            text = format!(
+                "void {}(void* target, void* source, int line) {{\n\
+                 {t} *to = target;\n{t} *from = source;\n",
+                CodeGenVisitor::record_function(*number, "copy"),
+                t = type_text
+            );
+            for (name, field_type, sizes) in fields {
+                let field = Address::field_format(name);
+                text = match field_type {
+                    NodeType::ArrayOf(..) => {
+                        let size_list = |record: &str| {
+                            let sizes: Vec<String> = (0..sizes.len())
+                                .map(|dimension| {
+                                    format!(
+                                        "{}->{}",
+                                        record,
+                                        Address::size_name(&field, dimension)
+                                    )
+                                })
+                                .collect();
+                            format!("(int[]){{{}}}", sizes.join(", "))
+                        };
+                        format!(
+                            "{}copy_array(to->{f}, from->{f}, {}, {}, {}, sizeof({}), {}, line);\n",
+                            text,
+                            size_list("to"),
+                            size_list("from"),
+                            sizes.len(),
+                            CodeGenVisitor::type_conversion_from_node_type(
+                                CodeGenVisitor::innermost_element_type(
+                                    field_type
+                                )
+                            ),
+                            CodeGenVisitor::element_copy(field_type),
+                            f = field
+                        )
+                    }
+                    NodeType::Simple(SimpleType::String) => format!(
+                        "{}to->{f} = mp_string_assign(to->{f}, from->{f}, line);\n",
+                        text,
+                        f = field
+                    ),
+                    NodeType::Record(number, _fields)
+                        if CodeGenVisitor::has_buffers(field_type) =>
+                    {
+                        format!(
+                            "{}{}(&to->{f}, &from->{f}, line);\n",
+                            text,
+                            CodeGenVisitor::record_function(*number, "copy"),
+                            f = field
+                        )
+                    }
+                    _ => format!("{}to->{f} = from->{f};\n", text, f = field),
+                };
+            }
+        }
+        format!("{}}}\n", text)
+    }
+
+    fn build_name_table(number: u64, members: &Vec<String>) -> String {
+        let names: Vec<String> =
+            members.iter().map(|name| format!("\"{}\"", name)).collect();
+        format!(
+            "char *{}[] = {{{}}};\n",
+            CodeGenVisitor::enumeration_names(number),
+            names.join(", ")
+        )
+    }
+
+    // Prototypes let the definitions call each other in any order.
+    fn build_prototype(&mut self, node: &TypedSubroutine) -> String {
+        let heading = match node {
+            TypedSubroutine::Function(address, params, _body, out_type) => {
+                self.build_heading(address, params, Some(out_type))
+            }
+            TypedSubroutine::Procedure(address, params, _body) => {
+                self.build_heading(address, params, None)
+            }
+        };
+        format!("{};\n", heading)
+    }
 }
 
 impl TypedVisitor for CodeGenVisitor {
     fn visit_ast(&mut self, node: &TypedAST) {
-        let TypedAST::Program(_token, subroutines, main_block) = node;
+        let TypedAST::Program(
+            _token,
+            type_definitions,
+            subroutines,
+            main_block,
+        ) = node;
+        let string_overflow = match self.options.string_overflow {
+            StringOverflow::Truncate => "MP_TRUNCATE",
+            StringOverflow::Error => "MP_ERROR",
+            StringOverflow::Grow => "MP_GROW",
+        };
+        self.declaration_buffer.push_str(
+            format!(
+                "#define MP_MAX_STRING_SIZE {}\n#define MP_STRING_OVERFLOW {}\n",
+                self.max_string_size, string_overflow
+            )
+            .as_str(),
+        );
         self.insert_runtime("src/runtime.c");
         self.declaration_buffer
             .push_str("short r0 = 0;\nshort r1 = 1;\n");
+        for definition in type_definitions {
+            let definition = match definition {
+                NodeType::Enumeration(number, members) => {
+                    CodeGenVisitor::build_name_table(*number, members)
+                }
+                _ if CodeGenVisitor::has_buffers(definition) => format!(
+                    "{}{}{}",
+                    CodeGenVisitor::build_struct(definition),
+                    CodeGenVisitor::build_record_init(definition),
+                    CodeGenVisitor::build_record_copy(definition)
+                ),
+                _ => CodeGenVisitor::build_struct(definition),
+            };
+            self.declaration_buffer.push_str(definition.as_str());
+        }
+        for sub in subroutines {
+            let prototype = self.build_prototype(sub);
+            self.declaration_buffer.push_str(prototype.as_str());
+        }
         for sub in subroutines {
             self.visit_subroutine(sub);
         }
@@ -654,10 +1492,27 @@ impl TypedVisitor for CodeGenVisitor {
             TypedStatement::Return(token, value) => {
                 self.visit_return(token, value)
             }
+            TypedStatement::For(
+                variable,
+                start,
+                direction,
+                limit,
+                limit_copy,
+                body,
+            ) => self
+                .visit_for(variable, start, direction, limit, limit_copy, body),
+            TypedStatement::Repeat(body, condition) => {
+                self.visit_repeat(body, condition)
+            }
+            TypedStatement::Case(selector, arms, else_body) => {
+                self.visit_case(selector, arms, else_body)
+            }
             TypedStatement::While(condition, body) => {
                 self.visit_while(condition, body)
             }
-            TypedStatement::Write(args) => self.visit_write(args),
+            TypedStatement::Write(args, newline) => {
+                self.visit_write(args, *newline)
+            }
         }
     }
 
@@ -668,10 +1523,17 @@ impl TypedVisitor for CodeGenVisitor {
     ) {
         self.visit_expression(value);
         match &variable.node_type {
-            NodeType::ArrayOf(st) => {
-                self.assign_array(variable, value, st.clone())
+            NodeType::ArrayOf(..) => self.assign_array(variable, value),
+            NodeType::Record(..)
+                if CodeGenVisitor::has_buffers(&variable.node_type) =>
+            {
+                self.assign_record(variable, value)
             }
-            NodeType::Simple(_st) => self.assign_simple(variable, value),
+            // Records are C structs, so the assignment copies them.
+            NodeType::Simple(_)
+            | NodeType::Record(..)
+            | NodeType::Enumeration(..)
+            | NodeType::Subrange(..) => self.assign_simple(variable, value),
         }
     }
 
@@ -695,43 +1557,52 @@ impl TypedVisitor for CodeGenVisitor {
                         self.max_string_size
                     );
                     self.add_declaration(size_text);
+                    self.add_code(format!(
+                        "{} = mp_string_copy(\"\", 0);\n",
+                        identifier.address
+                    ));
                 }
-            }
-            TypedTypeDescription::Array(_token, size_expr) => {
-                self.visit_expression(size_expr);
-                match &identifier.node_type {
-                    NodeType::ArrayOf(t) => {
-                        self.declare(
-                            &identifier.address.clone(),
-                            &identifier.node_type,
-                        );
-                        let type_id = CodeGenVisitor::type_conversion(&t);
-                        let size_text = format!(
-                            "int {}_size = {};\n",
-                            identifier.address, size_expr.address
-                        );
-                        let alloc_text = format!(
-                            "{} = ({}*) malloc({} * sizeof({}));\n",
-                            identifier.address,
-                            type_id,
-                            size_expr.address,
-                            type_id
-                        );
-                        self.add_declaration(size_text);
-                        self.add_code(alloc_text);
-                        if t.clone() == SimpleType::String {
-                            let str_alloc_text = format!(
-                                "alloc_str_array({}, {}, {});\n",
-                                identifier.address.clone(),
-                                size_expr.address.clone(),
-                                self.max_string_size,
-                            );
-                            self.buffer.push_str(str_alloc_text.as_str());
-                        }
+                if let NodeType::Record(number, _fields) = &identifier.node_type
+                {
+                    if CodeGenVisitor::has_buffers(&identifier.node_type) {
+                        self.add_code(format!(
+                            "{}(&{});\n",
+                            CodeGenVisitor::record_function(*number, "init"),
+                            identifier.address
+                        ));
                     }
-                    _ => (), // Should not be possible
                 }
             }
+            TypedTypeDescription::Array(_node_type, sizes) => {
+                self.declare(&identifier.address, &identifier.node_type);
+                for (dimension, size) in sizes.iter().enumerate() {
+                    self.visit_expression(size);
+                    let size_address =
+                        identifier.address.dimension_size_format(dimension);
+                    self.add_declaration(format!("int {};\n", size_address));
+                    self.add_code(format!(
+                        "{} = {};\n",
+                        size_address, size.address
+                    ));
+                }
+                let element_type = CodeGenVisitor::innermost_element_type(
+                    &identifier.node_type,
+                );
+                let alloc_text = format!(
+                    "{} = ({}) alloc_array({}, {}, sizeof({}), {});\n",
+                    identifier.address,
+                    CodeGenVisitor::type_conversion_from_node_type(
+                        &identifier.node_type
+                    ),
+                    CodeGenVisitor::size_list(&identifier.address, sizes.len()),
+                    sizes.len(),
+                    CodeGenVisitor::type_conversion_from_node_type(
+                        element_type
+                    ),
+                    CodeGenVisitor::element_init(&identifier.node_type),
+                );
+                self.add_code(alloc_text);
+            }
         }
     }
     fn visit_if(
@@ -782,6 +1653,121 @@ impl TypedVisitor for CodeGenVisitor {
         self.add_code(format!("{}:\n", end_label));
     }
 
+    fn visit_repeat(
+        &mut self,
+        body: &Vec<TypedStatement>,
+        condition: &TypedExpression,
+    ) {
+        let loop_start_label = self.get_new_label();
+        self.add_code(format!("{}:\n", loop_start_label));
+        self.visit_block(body);
+        self.visit_expression(condition);
+        let jump_text = format!(
+            "if ({} != 1 ) {{ goto {}; }}\n",
+            condition.address, loop_start_label
+        );
+        self.add_code(jump_text);
+    }
+
+    // Single integer labels jump through a switch and ranges are tested
+    // after it, string labels are compared one at a time.
+    fn visit_case(
+        &mut self,
+        selector: &TypedExpression,
+        arms: &Vec<(Vec<TypedCaseLabel>, TypedStatement)>,
+        else_body: &Option<Box<TypedStatement>>,
+    ) {
+        self.visit_expression(selector);
+        let address = selector.address.clone();
+        let mut switch_text = String::new();
+        let mut tests_text = String::new();
+        let mut arm_labels = Vec::new();
+        for (labels, _body) in arms {
+            let arm_label = self.get_new_label();
+            for label in labels {
+                let text = match label {
+                    TypedCaseLabel::Range(low, high) if low == high => {
+                        switch_text.push_str(
+                            format!("case {}: goto {};\n", low, arm_label)
+                                .as_str(),
+                        );
+                        continue;
+                    }
+                    TypedCaseLabel::Range(low, high) => format!(
+                        "if ({} >= {} && {} <= {}) {{ goto {}; }}\n",
+                        address, low, address, high, arm_label
+                    ),
+                    TypedCaseLabel::String(lexeme) => format!(
+                        "if (strcmp({}, {}) == 0) {{ goto {}; }}\n",
+                        address, lexeme, arm_label
+                    ),
+                };
+                tests_text.push_str(text.as_str());
+            }
+            arm_labels.push(arm_label);
+        }
+        if !switch_text.is_empty() {
+            self.add_code(format!(
+                "switch ({}) {{\n{}}}\n",
+                address, switch_text
+            ));
+        }
+        self.add_code(tests_text);
+        let else_label = self.get_new_label();
+        let end_label = self.get_new_label();
+        self.add_code(format!("goto {};\n", else_label));
+        for ((_labels, body), arm_label) in arms.iter().zip(arm_labels) {
+            self.add_code(format!("{}:\n", arm_label));
+            self.visit_statement(body);
+            self.add_code(format!("goto {};\n", end_label));
+        }
+        self.add_code(format!("{}:\n", else_label));
+        if let Some(body) = else_body {
+            self.visit_statement(body);
+        }
+        self.add_code(format!("{}:\n", end_label));
+    }
+
+    // The limit is checked before stepping, so the control variable never
+    // steps past it and can not overflow.
+    fn visit_for(
+        &mut self,
+        variable: &TypedVariable,
+        start: &TypedExpression,
+        direction: &Token,
+        limit: &TypedExpression,
+        limit_copy: &Address,
+        body: &TypedStatement,
+    ) {
+        let (comparison, step) = match direction.token_kind {
+            TokenKind::Downto => ("<", "-"),
+            _ => (">", "+"),
+        };
+        let loop_start_label = self.get_new_label();
+        let end_label = self.get_new_label();
+        self.visit_expression(start);
+        self.visit_expression(limit);
+        self.declare(limit_copy, &NodeType::Simple(SimpleType::Integer));
+        self.add_code(format!("{} = {};\n", limit_copy, limit.address));
+        self.add_code(format!("{} = {};\n", variable.address, start.address));
+        self.add_code(format!(
+            "if ({} {} {}) {{ goto {}; }}\n",
+            variable.address, comparison, limit_copy, end_label
+        ));
+        self.add_code(format!("{}:\n", loop_start_label));
+        self.visit_statement(body);
+        self.add_code(format!(
+            "if ({} == {}) {{ goto {}; }}\n",
+            variable.address, limit_copy, end_label
+        ));
+        self.add_code(format!(
+            "{} = {} {} 1;\n",
+            variable.address, variable.address, step
+        ));
+        self.add_code(format!("goto {};\n", loop_start_label));
+        self.add_code(format!("{}:\n", end_label));
+    }
+
     fn visit_assert(&mut self, token: &Token, condition: &TypedExpression) {
         self.visit_expression(condition);
         let cond_addr = condition.address.clone();
@@ -803,15 +1789,46 @@ impl TypedVisitor for CodeGenVisitor {
         let text = format!("{}{};\n", address, args_text);
         self.add_code(text);
     }
-    fn visit_return(
-        &mut self,
-        _token: &Token,
-        value: &Option<TypedExpression>,
-    ) {
+    fn visit_return(&mut self, token: &Token, value: &Option<TypedExpression>) {
         let text = if let Some(return_val) = value {
             self.visit_expression(return_val);
-            format!("return {};", return_val.address)
+            self.free_string_copies(Some(&return_val.address));
+            match &self.result_type {
+                // The caller gets a copy of the sizes the function declares.
+                Some(result_type) => {
+                    let dimensions = CodeGenVisitor::dimensions(result_type);
+                    let sizes: Vec<String> = (0..dimensions)
+                        .map(|d| {
+                            format!(
+                                "*{}",
+                                CodeGenVisitor::result_size_format(d)
+                            )
+                        })
+                        .collect();
+                    format!(
+                        "return ({}) clone_array({}, (int[]){{{}}}, {}, {}, sizeof({}), {}, {}, {});",
+                        CodeGenVisitor::type_conversion_from_node_type(
+                            result_type
+                        ),
+                        return_val.address,
+                        sizes.join(", "),
+                        CodeGenVisitor::size_list(
+                            &return_val.address,
+                            dimensions
+                        ),
+                        dimensions,
+                        CodeGenVisitor::type_conversion_from_node_type(
+                            CodeGenVisitor::innermost_element_type(result_type)
+                        ),
+                        CodeGenVisitor::element_init(result_type),
+                        CodeGenVisitor::element_copy(result_type),
+                        token.row + 1
+                    )
+                }
+                None => format!("return {};", return_val.address),
+            }
         } else {
+            self.free_string_copies(None);
             String::from("return;")
         };
         self.add_code(text);
@@ -825,6 +1842,7 @@ impl TypedVisitor for CodeGenVisitor {
                     op,
                     &node.address,
                     &node.node_type,
+                    &node.token,
                 ),
             TypedExpressionStructure::Call(token, args) => self
                 .visit_call_expression(
@@ -833,8 +1851,29 @@ impl TypedVisitor for CodeGenVisitor {
                     &node.address,
                     &node.node_type,
                 ),
-            TypedExpressionStructure::Size(array_address) => {
-                self.visit_size(&array_address, &node.address)
+            TypedExpressionStructure::Conversion(expr) => {
+                self.visit_conversion(node, expr)
+            }
+            TypedExpressionStructure::Indexed(array, indices) => {
+                self.visit_expression(array);
+                for index in indices {
+                    self.visit_expression(index);
+                }
+                if self.options.bounds_checks {
+                    self.check_bounds(&node.address, &node.token);
+                }
+            }
+            TypedExpressionStructure::RuntimeCall(function, args) => {
+                self.visit_runtime_call(node, function, args)
+            }
+            TypedExpressionStructure::Size(array) => {
+                self.visit_size(array, &node.address)
+            }
+            TypedExpressionStructure::Step(ordinal, step) => {
+                self.visit_step(node, ordinal, *step)
+            }
+            TypedExpressionStructure::RangeCheck(value) => {
+                self.visit_range_check(node, value)
             }
             TypedExpressionStructure::Unary(expr) => {
                 self.visit_unary(node, expr)
@@ -842,6 +1881,9 @@ impl TypedVisitor for CodeGenVisitor {
             TypedExpressionStructure::Literal => {
                 self.visit_literal(&node.token, &node.address, &node.node_type)
             }
+            TypedExpressionStructure::Reference(var) => {
+                self.visit_variable(var)
+            }
             TypedExpressionStructure::Variable(var) => self.visit_variable(var),
         }
     }
@@ -1,6 +1,8 @@
 pub fn get_keywords<'a>() -> Vec<&'a str> {
     vec![
         "var",
+        "const",
+        "type",
         "and",
         "or",
         "not",
@@ -10,9 +12,16 @@ pub fn get_keywords<'a>() -> Vec<&'a str> {
         "of",
         "while",
         "do",
+        "for",
+        "to",
+        "downto",
+        "repeat",
+        "until",
+        "case",
         "begin",
         "end",
         "array",
+        "record",
         "procedure",
         "function",
         "program",
@@ -24,6 +33,6 @@ pub fn get_keywords<'a>() -> Vec<&'a str> {
 pub fn get_special_symbols<'a>() -> Vec<&'a str> {
     vec![
         "+", "-", "*", "/", "%", "=", "<>", "<", "<=", ">=", ">", "(", ")", "[", "]", ".", ",",
-        ";", ":", ":=",
+        ";", ":", ":=", "..",
     ]
 }
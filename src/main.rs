@@ -1,3 +1,4 @@
+use crate::options::Options;
 use crate::parser::Parser;
 use crate::visitor::*;
 use std::env;
@@ -8,6 +9,7 @@ mod opkind;
 mod ast;
 mod codegenvisitor;
 mod constants;
+mod options;
 mod parser;
 mod printvisitor;
 mod scanner;
@@ -22,9 +24,12 @@ fn main() {
     let args: Vec<String> = env::args().collect();
     if let Some(file_in) = args.get(1) {
         if let Some(file_out) = args.get(2) {
-            match compile(file_in, file_out) {
-                Ok(()) => (),
-                Err(_field) => print!("Error occurred"),
+            match Options::parse(&args[3..]) {
+                Ok(options) => match compile(file_in, file_out, options) {
+                    Ok(()) => (),
+                    Err(_field) => print!("Error occurred"),
+                },
+                Err(msg) => println!("{}", msg),
             }
         } else {
             println!("No output file.");
@@ -34,7 +39,11 @@ fn main() {
     }
 }
 
-fn compile(file_in: &String, file_out: &String) -> std::io::Result<()> { 
+fn compile(
+    file_in: &String,
+    file_out: &String,
+    options: Options,
+) -> std::io::Result<()> {
     println!("Mini-Pascal compiler by Vili Lipo, Helsinki 2020.\n");
     let maybe_source = source::read_file(file_in);
     if let Ok(s) = maybe_source {
@@ -52,7 +61,7 @@ fn compile(file_in: &String, file_out: &String) -> std::io::Result<()> {
                     println!("{}", e)
                 }
                 if tf.get_errors().len() == 0 && parser.errors.len() == 0 {
-                    let mut cv = codegenvisitor::CodeGenVisitor::new();
+                    let mut cv = codegenvisitor::CodeGenVisitor::new(options);
                     cv.visit_ast(&typedast);
                     let output = cv.get_output();
                     let mut file = File::create(file_out)?;
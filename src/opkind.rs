@@ -1,3 +1,4 @@
+#[derive(Clone)]
 pub enum OpKind {
     Addition,
     NumArithmetic(NumArithmetic),
@@ -6,12 +7,14 @@ pub enum OpKind {
     BoolArithmetic(BoolArithmetic),
 }
 
+#[derive(Clone)]
 pub enum NumArithmetic {
     Minus,
     Division,
     Multi,
 }
 
+#[derive(Clone)]
 pub enum Relational {
     Equal,
     NotEqual,
@@ -21,6 +24,7 @@ pub enum Relational {
     LargerE,
 }
 
+#[derive(Clone)]
 pub enum BoolArithmetic {
     Or,
     And,
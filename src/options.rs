@@ -0,0 +1,75 @@
+// Compile options are given after the output file, for example
+// "mini-pascal-compiler in.minipascal out.c --no-range-checks".
+pub struct Options {
+    // Values given to subranges and to chr are checked at run time. Without
+    // the checks chr wraps its argument into a char, chr(300) is chr(44).
+    pub range_checks: bool,
+    pub bounds_checks: bool,
+    pub string_overflow: StringOverflow,
+}
+
+// What happens to a string that does not fit into the maximum string size.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum StringOverflow {
+    Truncate,
+    Error,
+    Grow,
+}
+
+impl Options {
+    pub fn parse(flags: &[String]) -> Result<Options, String> {
+        let mut options = Options {
+            range_checks: true,
+            bounds_checks: true,
+            string_overflow: StringOverflow::Error,
+        };
+        for flag in flags {
+            match flag.as_str() {
+                "--no-range-checks" => options.range_checks = false,
+                "--no-bounds-checks" => options.bounds_checks = false,
+                "--string-overflow=truncate" => {
+                    options.string_overflow = StringOverflow::Truncate
+                }
+                "--string-overflow=error" => {
+                    options.string_overflow = StringOverflow::Error
+                }
+                "--string-overflow=grow" => {
+                    options.string_overflow = StringOverflow::Grow
+                }
+                _ => return Err(format!("Unknown option {}", flag)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let options = Options::parse(&[]).unwrap();
+        assert!(options.range_checks);
+        assert!(options.bounds_checks);
+        let flags = vec![String::from("--no-range-checks")];
+        let options = Options::parse(&flags).unwrap();
+        assert!(!options.range_checks);
+        assert!(options.bounds_checks);
+        let flags = vec![String::from("--no-bounds-checks")];
+        let options = Options::parse(&flags).unwrap();
+        assert!(options.range_checks);
+        assert!(!options.bounds_checks);
+        assert_eq!(options.string_overflow, StringOverflow::Error);
+        let flags = vec![String::from("--string-overflow=grow")];
+        let options = Options::parse(&flags).unwrap();
+        assert_eq!(options.string_overflow, StringOverflow::Grow);
+        let flags = vec![String::from("--string-overflow=truncate")];
+        let options = Options::parse(&flags).unwrap();
+        assert_eq!(options.string_overflow, StringOverflow::Truncate);
+        let flags = vec![String::from("--fast")];
+        assert!(Options::parse(&flags).is_err());
+        let flags = vec![String::from("--string-overflow=wrap")];
+        assert!(Options::parse(&flags).is_err());
+    }
+}
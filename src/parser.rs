@@ -64,10 +64,27 @@ impl Parser {
                 if let Err(msg) = self.skip_delimiter(TokenKind::SemiColon) {
                     self.handle_error(msg.as_str());
                 }
+                let mut declarations = Vec::new();
+                loop {
+                    match self.ctt {
+                        TokenKind::Const => {
+                            declarations.append(&mut self.constant_section())
+                        }
+                        TokenKind::Type => {
+                            declarations.append(&mut self.type_section())
+                        }
+                        _ => break,
+                    }
+                }
                 if let Some(subroutines) = self.functions_and_procedures() {
                     if let Some(main_block) = self.block() {
                         if let Statement::Block(block) = main_block {
-                            return Some(AST::Program(id, subroutines, block));
+                            return Some(AST::Program(
+                                id,
+                                declarations,
+                                subroutines,
+                                block,
+                            ));
                         }
                     }
                 }
@@ -99,24 +116,17 @@ impl Parser {
         Some(subroutines)
     }
 
-    fn parameters(&mut self) -> Option<Vec<(Token, TypeDescription)>> {
+    fn parameters(
+        &mut self,
+    ) -> Option<Vec<(Token, TypeDescription, ParameterMode)>> {
         let mut parameters = Vec::new();
         self.next_token();
         loop {
             match self.ctt {
-                TokenKind::Identifier => {
-                    let id = self.current_token.clone();
-                    self.next_token();
-                    if let Err(msg) = self.skip_delimiter(TokenKind::Colon) {
-                        self.handle_error(msg.as_str());
-                    } else {
-                        if let Some(type_construct) = self.type_construct() {
-                            let param = (id, type_construct);
-                            parameters.push(param);
-                        }
-                    }
+                TokenKind::Var | TokenKind::Identifier => {
+                    parameters.append(&mut self.parameter_group());
                 }
-                TokenKind::Comma => self.next_token(),
+                TokenKind::Comma | TokenKind::SemiColon => self.next_token(),
                 TokenKind::CloseBracket => {
                     self.next_token();
                     break;
@@ -127,6 +137,133 @@ impl Parser {
         Some(parameters)
     }
 
+    // Parses "[var] id {, id} : type", the ids share the mode and the type.
+    fn parameter_group(
+        &mut self,
+    ) -> Vec<(Token, TypeDescription, ParameterMode)> {
+        let mut group = Vec::new();
+        let mode = if let TokenKind::Var = self.ctt {
+            self.next_token();
+            ParameterMode::Var
+        } else {
+            ParameterMode::Value
+        };
+        let mut ids = Vec::new();
+        while let TokenKind::Identifier = self.ctt {
+            ids.push(self.current_token.clone());
+            self.next_token();
+            if let TokenKind::Comma = self.ctt {
+                self.next_token();
+            } else {
+                break;
+            }
+        }
+        if let Err(msg) = self.skip_delimiter(TokenKind::Colon) {
+            self.handle_error(msg.as_str());
+        } else if let Some(type_construct) = self.type_construct() {
+            for id in ids {
+                group.push((id, type_construct.clone(), mode));
+            }
+        }
+        group
+    }
+
+    // Parses "record id {, id} : type {; id {, id} : type} [;] end".
+    fn record_type(&mut self) -> Option<TypeDescription> {
+        let record_token = self.current_token.clone();
+        self.next_token();
+        let mut fields = Vec::new();
+        loop {
+            match self.ctt {
+                TokenKind::Identifier => {
+                    for (id, type_description, _mode) in self.parameter_group()
+                    {
+                        fields.push((id, type_description));
+                    }
+                }
+                TokenKind::SemiColon => self.next_token(),
+                TokenKind::End => {
+                    self.next_token();
+                    break;
+                }
+                _ => {
+                    self.handle_error("Expected a field or end in record type");
+                    return None;
+                }
+            }
+        }
+        Some(TypeDescription::Record(record_token, fields))
+    }
+
+    // Parses "array [ size {, size} ] of type". "array[n, m] of t" is short
+    // for "array[n] of array[m] of t". Without the sizes it is an open array.
+    fn array_type(&mut self) -> Option<TypeDescription> {
+        let array_token = self.current_token.clone();
+        self.next_token();
+        if let TokenKind::Of = self.ctt {
+            self.next_token();
+            let element = self.type_construct()?;
+            return Some(TypeDescription::OpenArray(
+                array_token,
+                Box::from(element),
+            ));
+        }
+        if let Err(msg) = self.skip_delimiter(TokenKind::OpenSquareBracket) {
+            self.handle_error(msg.as_str());
+            return None;
+        }
+        let sizes = self.expression_list()?;
+        if let Err(msg) = self.skip_delimiter(TokenKind::CloseSquareBracket) {
+            self.handle_error(msg.as_str());
+            return None;
+        }
+        if let Err(msg) = self.skip_delimiter(TokenKind::Of) {
+            self.handle_error(msg.as_str());
+            return None;
+        }
+        let mut array = self.type_construct()?;
+        for size in sizes.into_iter().rev() {
+            array = TypeDescription::Array(Box::from(array), size);
+        }
+        Some(array)
+    }
+
+    // Parses "( id {, id} )".
+    fn enumeration_type(&mut self) -> Option<TypeDescription> {
+        let bracket_token = self.current_token.clone();
+        self.next_token();
+        let mut members = Vec::new();
+        while let TokenKind::Identifier = self.ctt {
+            members.push(self.current_token.clone());
+            self.next_token();
+            if let TokenKind::Comma = self.ctt {
+                self.next_token();
+            } else {
+                break;
+            }
+        }
+        if let Err(msg) = self.skip_delimiter(TokenKind::CloseBracket) {
+            self.handle_error(msg.as_str());
+            return None;
+        }
+        if members.is_empty() {
+            self.handle_error("Enumeration type without members");
+            return None;
+        }
+        Some(TypeDescription::Enumeration(bracket_token, members))
+    }
+
+    // Parses "..high" after the lower bound of a subrange.
+    fn subrange_type(&mut self, low: Expression) -> Option<TypeDescription> {
+        let range_token = self.current_token.clone();
+        if let Err(msg) = self.skip_delimiter(TokenKind::DoubleDot) {
+            self.handle_error(msg.as_str());
+            return None;
+        }
+        let high = self.expression()?;
+        Some(TypeDescription::Subrange(range_token, low, high))
+    }
+
     fn function(&mut self) -> Option<Subroutine> {
         self.next_token();
         if let TokenKind::Identifier = self.ctt {
@@ -141,19 +278,28 @@ impl Parser {
                             self.skip_delimiter(TokenKind::SemiColon)
                         {
                             self.handle_error(msg.as_str());
+                        } else if self.forward_directive() {
+                            return Some(Subroutine::ForwardFunction(
+                                token,
+                                parameters,
+                                type_construct,
+                            ));
                         } else {
+                            let mut body = self.declaration_part();
                             if let Some(block) = self.block() {
                                 if let Err(msg) =
                                     self.skip_delimiter(TokenKind::SemiColon)
                                 {
                                     self.handle_error(msg.as_str());
                                 } else {
-                                    if let Statement::Block(is_block_ok) = block
+                                    if let Statement::Block(mut is_block_ok) =
+                                        block
                                     {
+                                        body.append(&mut is_block_ok);
                                         return Some(Subroutine::Function(
                                             token,
                                             parameters,
-                                            is_block_ok,
+                                            body,
                                             type_construct,
                                         ));
                                     }
@@ -175,18 +321,22 @@ impl Parser {
             if let Some(parameters) = self.parameters() {
                 if let Err(msg) = self.skip_delimiter(TokenKind::SemiColon) {
                     self.handle_error(msg.as_str());
+                } else if self.forward_directive() {
+                    return Some(Subroutine::ForwardProcedure(
+                        token, parameters,
+                    ));
                 } else {
+                    let mut body = self.declaration_part();
                     if let Some(block) = self.block() {
-                        if let Statement::Block(is_block_ok) = block {
+                        if let Statement::Block(mut is_block_ok) = block {
                             if let Err(msg) =
                                 self.skip_delimiter(TokenKind::SemiColon)
                             {
                                 self.handle_error(msg.as_str());
                             }
+                            body.append(&mut is_block_ok);
                             return Some(Subroutine::Procedure(
-                                token,
-                                parameters,
-                                is_block_ok,
+                                token, parameters, body,
                             ));
                         }
                     }
@@ -196,6 +346,100 @@ impl Parser {
         return None;
     }
 
+    // Pascal-style declarations between a heading and its body: the local
+    // variables first and then the nested subroutines. They are returned as
+    // statements that precede the ones of the body.
+    fn declaration_part(&mut self) -> Vec<Statement> {
+        let mut declarations = Vec::new();
+        loop {
+            match self.ctt {
+                TokenKind::Const => {
+                    declarations.append(&mut self.constant_section())
+                }
+                TokenKind::Type => {
+                    declarations.append(&mut self.type_section())
+                }
+                TokenKind::Var => {
+                    if let Some(declaration) = self.declaration_stmnt() {
+                        declarations.push(declaration);
+                    }
+                    if let Err(msg) = self.skip_delimiter(TokenKind::SemiColon)
+                    {
+                        self.handle_error(msg.as_str());
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+        if let Some(subroutines) = self.functions_and_procedures() {
+            if !subroutines.is_empty() {
+                declarations.push(Statement::Subroutines(subroutines));
+            }
+        }
+        declarations
+    }
+
+    // Parses "const id = expression; {id = expression;}".
+    fn constant_section(&mut self) -> Vec<Statement> {
+        let mut constants = Vec::new();
+        self.next_token();
+        while let TokenKind::Identifier = self.ctt {
+            let id = self.current_token.clone();
+            self.next_token();
+            if let Err(msg) = self.skip_delimiter(TokenKind::Equal) {
+                self.handle_error(msg.as_str());
+                break;
+            }
+            if let Some(value) = self.expression() {
+                constants.push(Statement::Constant(id, value));
+            }
+            if let Err(msg) = self.skip_delimiter(TokenKind::SemiColon) {
+                self.handle_error(msg.as_str());
+                break;
+            }
+        }
+        constants
+    }
+
+    // Parses "type id = type; {id = type;}".
+    fn type_section(&mut self) -> Vec<Statement> {
+        let mut types = Vec::new();
+        self.next_token();
+        while let TokenKind::Identifier = self.ctt {
+            let id = self.current_token.clone();
+            self.next_token();
+            if let Err(msg) = self.skip_delimiter(TokenKind::Equal) {
+                self.handle_error(msg.as_str());
+                break;
+            }
+            if let Some(type_description) = self.type_construct() {
+                types.push(Statement::TypeDeclaration(id, type_description));
+            }
+            if let Err(msg) = self.skip_delimiter(TokenKind::SemiColon) {
+                self.handle_error(msg.as_str());
+                break;
+            }
+        }
+        types
+    }
+
+    // "forward" is a directive rather than a reserved word, so it is
+    // recognized by its lexeme where a body would otherwise begin.
+    fn forward_directive(&mut self) -> bool {
+        if self.ctt == TokenKind::Identifier
+            && self.current_token.lexeme == "forward"
+        {
+            self.next_token();
+            if let Err(msg) = self.skip_delimiter(TokenKind::SemiColon) {
+                self.handle_error(msg.as_str());
+            }
+            true
+        } else {
+            false
+        }
+    }
+
     fn block(&mut self) -> Option<Statement> {
         self.next_token();
         let mut statements = Vec::new();
@@ -233,6 +477,9 @@ impl Parser {
             TokenKind::Identifier => self.assign_or_call_stmnt(),
             TokenKind::If => self.if_stmnt(),
             TokenKind::While => self.while_stmnt(),
+            TokenKind::For => self.for_stmnt(),
+            TokenKind::Repeat => self.repeat_stmnt(),
+            TokenKind::Case => self.case_stmnt(),
             TokenKind::Begin => self.block(),
             TokenKind::Return => self.return_stmnt(),
             TokenKind::Assert => self.assert_stmnt(),
@@ -266,6 +513,151 @@ impl Parser {
         None
     }
 
+    // Parses "for id := expression (to | downto) expression do statement".
+    fn for_stmnt(&mut self) -> Option<Statement> {
+        if let TokenKind::For = self.ctt {
+            self.next_token();
+            if let TokenKind::Identifier = self.ctt {
+                let id = self.current_token.clone();
+                self.next_token();
+                if let Err(msg) = self.skip_delimiter(TokenKind::Assign) {
+                    self.handle_error(msg.as_str());
+                    return None;
+                }
+                let start = self.expression()?;
+                let direction = self.current_token.clone();
+                match self.ctt {
+                    TokenKind::To | TokenKind::Downto => self.next_token(),
+                    _ => {
+                        let text = format!(
+                            "Expected To or Downto got {}",
+                            self.current_token.token_kind
+                        );
+                        self.handle_error(text.as_str());
+                        return None;
+                    }
+                }
+                let limit = self.expression()?;
+                if let Err(msg) = self.skip_delimiter(TokenKind::Do) {
+                    self.handle_error(msg.as_str());
+                } else if let Some(statement) = self.statement() {
+                    return Some(Statement::For(
+                        id,
+                        start,
+                        direction,
+                        limit,
+                        Box::from(statement),
+                    ));
+                } else {
+                    self.handle_error("Missing for body");
+                }
+            } else {
+                self.handle_error("Missing for loop control variable");
+            }
+        }
+        None
+    }
+
+    // Parses "repeat statement {; statement} until expression", the
+    // semicolon before until is optional.
+    fn repeat_stmnt(&mut self) -> Option<Statement> {
+        if let TokenKind::Repeat = self.ctt {
+            self.next_token();
+            let mut statements = Vec::new();
+            loop {
+                match self.ctt {
+                    TokenKind::Until => {
+                        self.next_token();
+                        break;
+                    }
+                    TokenKind::SemiColon => self.next_token(),
+                    TokenKind::Eof => {
+                        self.handle_error("Unexpected eof");
+                        return None;
+                    }
+                    _ => {
+                        if let Some(statement) = self.statement() {
+                            statements.push(statement);
+                            if self.ctt != TokenKind::Until {
+                                if let Err(msg) =
+                                    self.skip_delimiter(TokenKind::SemiColon)
+                                {
+                                    self.handle_error(msg.as_str());
+                                }
+                            }
+                        } else {
+                            self.next_token();
+                        }
+                    }
+                }
+            }
+            if let Some(expression) = self.expression() {
+                return Some(Statement::Repeat(statements, expression));
+            }
+        }
+        None
+    }
+
+    // Parses "case expression of arm {; arm} [else statement] [;] end".
+    fn case_stmnt(&mut self) -> Option<Statement> {
+        if let TokenKind::Case = self.ctt {
+            self.next_token();
+            let selector = self.expression()?;
+            if let Err(msg) = self.skip_delimiter(TokenKind::Of) {
+                self.handle_error(msg.as_str());
+                return None;
+            }
+            let mut arms = Vec::new();
+            let mut else_body = None;
+            loop {
+                match self.ctt {
+                    TokenKind::End => {
+                        self.next_token();
+                        break;
+                    }
+                    TokenKind::SemiColon => self.next_token(),
+                    TokenKind::Else => {
+                        self.next_token();
+                        else_body = Some(Box::from(self.statement()?));
+                    }
+                    TokenKind::Eof => {
+                        self.handle_error("Unexpected eof");
+                        return None;
+                    }
+                    _ => arms.push(self.case_arm()?),
+                }
+            }
+            return Some(Statement::Case(selector, arms, else_body));
+        }
+        None
+    }
+
+    // Parses "label {, label} : statement", where a label is either a
+    // single value or a range "low..high".
+    fn case_arm(&mut self) -> Option<(Vec<CaseLabel>, Statement)> {
+        let mut labels = Vec::new();
+        loop {
+            let low = self.expression()?;
+            if let TokenKind::DoubleDot = self.ctt {
+                self.next_token();
+                labels.push(CaseLabel::Range(low, self.expression()?));
+            } else {
+                labels.push(CaseLabel::Single(low));
+            }
+            if let TokenKind::Comma = self.ctt {
+                self.next_token();
+            } else {
+                break;
+            }
+        }
+        if let Err(msg) = self.skip_delimiter(TokenKind::Colon) {
+            self.handle_error(msg.as_str());
+            return None;
+        }
+        let statement = self.statement()?;
+        Some((labels, statement))
+    }
+
     fn if_stmnt(&mut self) -> Option<Statement> {
         if let TokenKind::If = self.ctt {
             self.next_token();
@@ -305,37 +697,20 @@ impl Parser {
             TokenKind::Identifier => {
                 let type_token = self.current_token.clone();
                 self.next_token();
-                Some(TypeDescription::Simple(type_token))
-            }
-            TokenKind::Array => {
-                self.next_token();
-                if let Err(msg) =
-                    self.skip_delimiter(TokenKind::OpenSquareBracket)
-                {
-                    self.handle_error(msg.as_str());
-                    None
-                } else if let Some(expression) = self.expression() {
-                    if let Err(msg) =
-                        self.skip_delimiter(TokenKind::CloseSquareBracket)
-                    {
-                        self.handle_error(msg.as_str());
-                        None
-                    } else if let Err(msg) = self.skip_delimiter(TokenKind::Of)
-                    {
-                        self.handle_error(msg.as_str());
-                        None
-                    } else if let TokenKind::Identifier = self.ctt {
-                        let type_token = self.current_token.clone();
-                        self.next_token();
-                        Some(TypeDescription::Array(type_token, expression))
-                    } else {
-                        self.handle_error("Missing simple type for array type");
-                        None
-                    }
+                if let TokenKind::DoubleDot = self.ctt {
+                    let low = Variable::Simple(type_token);
+                    self.subrange_type(Expression::Variable(Box::from(low)))
                 } else {
-                    None
+                    Some(TypeDescription::Simple(type_token))
                 }
             }
+            TokenKind::IntegerLiteral | TokenKind::Minus => {
+                let low = self.expression()?;
+                self.subrange_type(low)
+            }
+            TokenKind::Record => self.record_type(),
+            TokenKind::OpenBracket => self.enumeration_type(),
+            TokenKind::Array => self.array_type(),
             _ => {
                 self.handle_error("No type given");
                 None
@@ -434,95 +809,69 @@ impl Parser {
     }
 
     fn expression(&mut self) -> Option<Expression> {
-        match self.simple_expression() {
-            Some(left_sub_expr) => match self.current_token.token_kind {
-                TokenKind::Equal
-                | TokenKind::SmallerThan
-                | TokenKind::NotEqual
-                | TokenKind::LargerThan
-                | TokenKind::ESmallerThan
-                | TokenKind::ELargerThan => {
-                    let expr_token = self.current_token.clone();
-                    self.next_token();
-                    if let Some(right_sub_expr) = self.simple_expression() {
-                        Some(Expression::Binary(
-                            Box::from(left_sub_expr),
-                            Box::from(right_sub_expr),
-                            expr_token,
-                        ))
-                    } else {
-                        None
-                    }
-                }
-                _ => Some(left_sub_expr),
-            },
-            None => None,
-        }
+        let lhs = self.simple_expression()?;
+        self.left_associative_chain(
+            lhs,
+            Parser::simple_expression,
+            &[
+                TokenKind::Equal,
+                TokenKind::SmallerThan,
+                TokenKind::NotEqual,
+                TokenKind::LargerThan,
+                TokenKind::ESmallerThan,
+                TokenKind::ELargerThan,
+            ],
+        )
     }
 
     fn simple_expression(&mut self) -> Option<Expression> {
-        match self.term() {
-            Some(left_term) => match self.current_token.token_kind {
-                TokenKind::Plus | TokenKind::Minus | TokenKind::Or => {
-                    let op_token = self.current_token.clone();
-                    self.next_token();
-                    if let Some(right_term) = self.term() {
-                        Some(Expression::Binary(
-                            Box::from(left_term),
-                            Box::from(right_term),
-                            op_token,
-                        ))
-                    } else {
-                        None
-                    }
-                }
-                _ => Some(left_term),
-            },
-            None => None,
-        }
+        let lhs = self.term()?;
+        self.left_associative_chain(
+            lhs,
+            Parser::term,
+            &[TokenKind::Plus, TokenKind::Minus, TokenKind::Or],
+        )
     }
 
     fn term(&mut self) -> Option<Expression> {
-        match self.factor() {
-            Some(lhs_node) => match self.current_token.token_kind {
-                TokenKind::Multi
-                | TokenKind::Division
-                | TokenKind::Modulo
-                | TokenKind::And => {
-                    let term_token = self.current_token.clone();
-                    self.next_token();
-                    if let Some(rhs_node) = self.factor() {
-                        Some(Expression::Binary(
-                            Box::from(lhs_node),
-                            Box::from(rhs_node),
-                            term_token,
-                        ))
-                    } else {
-                        None
-                    }
-                }
-                _ => Some(lhs_node),
-            },
-            None => None,
+        let lhs = self.factor()?;
+        self.left_associative_chain(
+            lhs,
+            Parser::factor,
+            &[
+                TokenKind::Multi,
+                TokenKind::Division,
+                TokenKind::Modulo,
+                TokenKind::And,
+            ],
+        )
+    }
+
+    // Folds "lhs op operand op operand ..." into nested Binary nodes so that
+    // operators of the same precedence level associate to the left.
+    fn left_associative_chain(
+        &mut self,
+        lhs: Expression,
+        operand: fn(&mut Parser) -> Option<Expression>,
+        operators: &[TokenKind],
+    ) -> Option<Expression> {
+        let mut chain = lhs;
+        while operators.contains(&self.ctt) {
+            let op_token = self.current_token.clone();
+            self.next_token();
+            let rhs = operand(self)?;
+            chain =
+                Expression::Binary(Box::from(chain), Box::from(rhs), op_token);
         }
+        Some(chain)
     }
 
     fn factor(&mut self) -> Option<Expression> {
         match self.current_token.token_kind {
-            TokenKind::Identifier => match self.variable_or_call() {
-                Some(id_construct) => match id_construct {
-                    Expression::Call(token, args) => {
-                        Some(Expression::Call(token, args))
-                    }
-                    Expression::Variable(node) => {
-                        Some(Expression::Variable(node))
-                    }
-                    _ => None,
-                },
-                None => None,
-            },
+            TokenKind::Identifier => self.variable_or_call(),
             TokenKind::RealLiteral
             | TokenKind::StringLiteral
+            | TokenKind::CharLiteral
             | TokenKind::IntegerLiteral => {
                 let token = self.current_token.clone();
                 self.next_token();
@@ -542,7 +891,7 @@ impl Parser {
                     }
                 }
             }
-            TokenKind::Not => {
+            TokenKind::Not | TokenKind::Plus | TokenKind::Minus => {
                 let token = self.current_token.clone();
                 self.next_token();
                 if let Some(rhs_node) = self.factor() {
@@ -569,51 +918,120 @@ impl Parser {
                 self.next_token();
                 match self.current_token.token_kind {
                     TokenKind::OpenSquareBracket => {
-                        self.next_token();
-                        if let Some(expr) = self.expression() {
-                            if let Err(msg) = self
-                                .skip_delimiter(TokenKind::CloseSquareBracket)
-                            {
-                                self.handle_error(msg.as_str());
-                                None
-                            } else {
-                                Some(Expression::Variable(Box::from(
-                                    Variable::Indexed(old_token, expr),
-                                )))
-                            }
-                        } else {
-                            self.handle_error("Missing array index expression");
-                            None
-                        }
+                        let indices = self.indices()?;
+                        self.field_selections(Variable::Indexed(
+                            old_token, indices,
+                        ))
                     }
                     TokenKind::OpenBracket => {
                         if let Some(args) = self.arguments() {
-                            Some(Expression::Call(old_token, args))
+                            self.call_selections(
+                                &old_token,
+                                Expression::Call(old_token.clone(), args),
+                            )
                         } else {
                             None
                         }
                     }
-                    TokenKind::Dot => {
+                    _ => self.field_selections(Variable::Simple(old_token)),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Parses "[ index {, index} ]" for each of the brackets that follow.
+    fn indices(&mut self) -> Option<Vec<Expression>> {
+        let mut indices = Vec::new();
+        while let TokenKind::OpenSquareBracket = self.ctt {
+            self.next_token();
+            indices.append(&mut self.expression_list()?);
+            if let Err(msg) = self.skip_delimiter(TokenKind::CloseSquareBracket)
+            {
+                self.handle_error(msg.as_str());
+                return None;
+            }
+        }
+        Some(indices)
+    }
+
+    fn expression_list(&mut self) -> Option<Vec<Expression>> {
+        let mut expressions = Vec::new();
+        loop {
+            if let Some(expression) = self.expression() {
+                expressions.push(expression);
+            } else {
+                self.handle_error("Missing array index expression");
+                return None;
+            }
+            if let TokenKind::Comma = self.ctt {
+                self.next_token();
+            } else {
+                return Some(expressions);
+            }
+        }
+    }
+
+    // "x.id" selects a field of a record, for other variables the type
+    // folder reads it as the call "id(x)" as in "a.size". A selected field
+    // can be indexed, "r.a[i]".
+    fn field_selections(&mut self, variable: Variable) -> Option<Expression> {
+        let mut variable = variable;
+        loop {
+            match self.ctt {
+                TokenKind::Dot => {
+                    self.next_token();
+                    if let TokenKind::Identifier = self.ctt {
+                        let field = self.current_token.clone();
                         self.next_token();
-                        let args = vec![Expression::Variable(Box::from(
-                            Variable::Simple(old_token),
-                        ))];
-                        if let TokenKind::Identifier =
-                            self.current_token.token_kind
-                        {
-                            let id_token = self.current_token.clone();
-                            self.next_token();
-                            Some(Expression::Call(id_token, args))
-                        } else {
-                            None
-                        }
+                        variable = Variable::Field(Box::from(variable), field);
+                    } else {
+                        self.handle_error("Expected an identifier after .");
+                        return None;
                     }
-                    _ => Some(Expression::Variable(Box::from(
-                        Variable::Simple(old_token),
-                    ))),
                 }
+                TokenKind::OpenSquareBracket => {
+                    let indices = self.indices()?;
+                    variable =
+                        Variable::FieldIndexed(Box::from(variable), indices);
+                }
+                _ => return Some(Expression::Variable(Box::from(variable))),
+            }
+        }
+    }
+
+    // The array a call returns can be indexed, "f(x)[i]", and its size
+    // selected, "f(x).size" is read as the call "size(f(x))".
+    fn call_selections(
+        &mut self,
+        id: &Token,
+        call: Expression,
+    ) -> Option<Expression> {
+        let mut expression = call;
+        loop {
+            match self.ctt {
+                TokenKind::OpenSquareBracket => {
+                    let indices = self.indices()?;
+                    expression = Expression::Index(
+                        Box::from(expression),
+                        id.clone(),
+                        indices,
+                    );
+                }
+                TokenKind::Dot => {
+                    self.next_token();
+                    if self.current_token.lexeme != "size" {
+                        self.handle_error(
+                            "Only size can be selected from a call",
+                        );
+                        return None;
+                    }
+                    let size = self.current_token.clone();
+                    self.next_token();
+                    expression = Expression::Call(size, vec![expression]);
+                }
+                _ => return Some(expression),
             }
-            _ => None,
         }
     }
 
@@ -628,12 +1046,12 @@ impl Parser {
                         Some(arguments)
                     }
                     _ => {
-                        if let Some(expr) = self.expression() {
+                        if let Some(expr) = self.argument() {
                             arguments.push(expr);
                             loop {
                                 if let TokenKind::Comma = self.ctt {
                                     self.next_token();
-                                    if let Some(expr) = self.expression() {
+                                    if let Some(expr) = self.argument() {
                                         arguments.push(expr)
                                     }
                                 } else {
@@ -653,4 +1071,514 @@ impl Parser {
             _ => None,
         }
     }
+
+    // An argument may have a field width and a precision, the type checker
+    // allows them only for write and writeln.
+    fn argument(&mut self) -> Option<Expression> {
+        let value = self.expression()?;
+        if let TokenKind::Colon = self.ctt {
+            let colon = self.current_token.clone();
+            self.next_token();
+            let width = self.expression()?;
+            let precision = if let TokenKind::Colon = self.ctt {
+                self.next_token();
+                Some(Box::new(self.expression()?))
+            } else {
+                None
+            };
+            Some(Expression::Formatted(
+                Box::new(value),
+                colon,
+                Box::new(width),
+                precision,
+            ))
+        } else {
+            Some(value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scanner;
+    use crate::source;
+
+    fn get_parser(text: &str) -> Parser {
+        let s = source::create_source(String::from(text));
+        let mut parser = Parser::new(scanner::build_scanner(s));
+        parser.next_token();
+        parser
+    }
+
+    fn render(expression: &Expression) -> String {
+        match expression {
+            Expression::Binary(lhs, rhs, op) => {
+                format!("({} {} {})", render(lhs), op.lexeme, render(rhs))
+            }
+            Expression::Unary(rhs, op) => {
+                format!("({} {})", op.lexeme, render(rhs))
+            }
+            Expression::Literal(t) => t.lexeme.clone(),
+            Expression::Variable(v) => match v.as_ref() {
+                Variable::Simple(t) => t.lexeme.clone(),
+                Variable::Indexed(t, indices) => {
+                    let indices: Vec<String> =
+                        indices.iter().map(render).collect();
+                    format!("{}[{}]", t.lexeme, indices.join(", "))
+                }
+                Variable::Field(record, field) => {
+                    let record = Expression::Variable(record.clone());
+                    format!("{}.{}", render(&record), field.lexeme)
+                }
+                Variable::FieldIndexed(array, indices) => {
+                    let array = Expression::Variable(array.clone());
+                    let indices: Vec<String> =
+                        indices.iter().map(render).collect();
+                    format!("{}[{}]", render(&array), indices.join(", "))
+                }
+            },
+            Expression::Call(t, args) => {
+                let args: Vec<String> = args.iter().map(render).collect();
+                format!("{}({})", t.lexeme, args.join(", "))
+            }
+            Expression::Index(array, _id, indices) => {
+                let indices: Vec<String> = indices.iter().map(render).collect();
+                format!("{}[{}]", render(array), indices.join(", "))
+            }
+            Expression::Formatted(value, _colon, width, precision) => {
+                match precision {
+                    Some(precision) => format!(
+                        "{}:{}:{}",
+                        render(value),
+                        render(width),
+                        render(precision)
+                    ),
+                    None => format!("{}:{}", render(value), render(width)),
+                }
+            }
+        }
+    }
+
+    fn parse_expression(text: &str) -> String {
+        let mut parser = get_parser(text);
+        let expression = parser.expression();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(parser.ctt, TokenKind::Eof);
+        render(&expression.expect("expression did not parse"))
+    }
+
+    #[test]
+    fn test_additive_chain_is_left_associative() {
+        assert_eq!(parse_expression("a + b + c"), "((a + b) + c)");
+        assert_eq!(parse_expression("a - b - c"), "((a - b) - c)");
+        assert_eq!(parse_expression("a - b + c - d"), "(((a - b) + c) - d)");
+    }
+
+    #[test]
+    fn test_multiplicative_chain_is_left_associative() {
+        assert_eq!(parse_expression("x * y * z"), "((x * y) * z)");
+        assert_eq!(parse_expression("x / y % z * w"), "(((x / y) % z) * w)");
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        let text = "1 + 2 + 3 + 4 + 5 + 6 + 7 + 8";
+        assert_eq!(
+            parse_expression(text),
+            "(((((((1 + 2) + 3) + 4) + 5) + 6) + 7) + 8)"
+        );
+    }
+
+    #[test]
+    fn test_mixed_precedence() {
+        assert_eq!(parse_expression("a + b * c"), "(a + (b * c))");
+        assert_eq!(parse_expression("a * b + c"), "((a * b) + c)");
+        assert_eq!(
+            parse_expression("a + b * c - d / e"),
+            "((a + (b * c)) - (d / e))"
+        );
+        assert_eq!(parse_expression("(a + b) * c"), "((a + b) * c)");
+    }
+
+    #[test]
+    fn test_relational_has_lowest_precedence() {
+        assert_eq!(parse_expression("a + 1 < b * 2"), "((a + 1) < (b * 2))");
+        assert_eq!(parse_expression("a = b <> c"), "((a = b) <> c)");
+    }
+
+    #[test]
+    fn test_boolean_operators_follow_arithmetic_levels() {
+        assert_eq!(
+            parse_expression("a or b and c or d"),
+            "((a or (b and c)) or d)"
+        );
+        assert_eq!(parse_expression("not a and b"), "((not a) and b)");
+    }
+
+    #[test]
+    fn test_chain_in_arguments_and_indices() {
+        assert_eq!(
+            parse_expression("f(a + b + c, A[i + j * k])"),
+            "f(((a + b) + c), a[(i + (j * k))])"
+        );
+    }
+
+    #[test]
+    fn test_sign_operators() {
+        assert_eq!(parse_expression("-1"), "(- 1)");
+        assert_eq!(parse_expression("-a + b"), "((- a) + b)");
+        assert_eq!(parse_expression("a * -b"), "(a * (- b))");
+        assert_eq!(parse_expression("-(a * b)"), "(- (a * b))");
+        assert_eq!(parse_expression("+a - -b"), "((+ a) - (- b))");
+    }
+
+    #[test]
+    fn test_missing_operand_is_an_error() {
+        let mut parser = get_parser("a + b +");
+        assert!(parser.expression().is_none());
+        assert!(!parser.errors.is_empty());
+    }
+
+    #[test]
+    fn test_forward_directive() {
+        let mut parser = get_parser(
+            "function f(a: integer): integer; forward;
+             procedure p(); forward;
+             procedure q(); begin end;
+             begin",
+        );
+        let subroutines = parser.functions_and_procedures().unwrap();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(subroutines.len(), 3);
+        match &subroutines[0] {
+            Subroutine::ForwardFunction(t, params, _out_type) => {
+                assert_eq!(t.lexeme, "f");
+                assert_eq!(params.len(), 1);
+            }
+            _ => panic!("expected a forward function"),
+        }
+        match &subroutines[1] {
+            Subroutine::ForwardProcedure(t, _params) => {
+                assert_eq!(t.lexeme, "p")
+            }
+            _ => panic!("expected a forward procedure"),
+        }
+        match &subroutines[2] {
+            Subroutine::Procedure(t, _params, _body) => {
+                assert_eq!(t.lexeme, "q")
+            }
+            _ => panic!("expected a procedure"),
+        }
+        assert_eq!(parser.ctt, TokenKind::Begin);
+    }
+
+    #[test]
+    fn test_declaration_part() {
+        let mut parser = get_parser(
+            "procedure p(n: integer);
+             var total: integer;
+               function q(): integer; begin return total; end;
+               procedure r(); begin end;
+             begin total := q(); end;",
+        );
+        let subroutine = parser.procedure().unwrap();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        if let Subroutine::Procedure(_t, _params, body) = subroutine {
+            assert_eq!(body.len(), 3);
+            match &body[0] {
+                Statement::Declaration(t, _description) => {
+                    assert_eq!(t.lexeme, "total")
+                }
+                _ => panic!("expected a declaration"),
+            }
+            match &body[1] {
+                Statement::Subroutines(nested) => assert_eq!(nested.len(), 2),
+                _ => panic!("expected nested subroutines"),
+            }
+            match &body[2] {
+                Statement::Assign(_variable, _value) => (),
+                _ => panic!("expected an assignment"),
+            }
+        } else {
+            panic!("expected a procedure");
+        }
+    }
+
+    #[test]
+    fn test_for_statement() {
+        let mut parser = get_parser("for i := n - 1 downto 0 do a := a + i");
+        let statement = parser.statement();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        match statement {
+            Some(Statement::For(id, start, direction, limit, _body)) => {
+                assert_eq!(id.lexeme, "i");
+                assert_eq!(render(&start), "(n - 1)");
+                assert_eq!(direction.token_kind, TokenKind::Downto);
+                assert_eq!(render(&limit), "0");
+            }
+            _ => panic!("expected a for statement"),
+        }
+    }
+
+    #[test]
+    fn test_repeat_statement() {
+        let mut parser =
+            get_parser("repeat a := a + 1; b := b - 1 until a > b");
+        let statement = parser.statement();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        match statement {
+            Some(Statement::Repeat(body, condition)) => {
+                assert_eq!(body.len(), 2);
+                assert_eq!(render(&condition), "(a > b)");
+            }
+            _ => panic!("expected a repeat statement"),
+        }
+        assert_eq!(parser.ctt, TokenKind::Eof);
+    }
+
+    #[test]
+    fn test_case_statement() {
+        let mut parser = get_parser(
+            "case n + 1 of
+               1, 3..5: a := 1;
+               -2: a := 2
+             else a := 3;
+             end",
+        );
+        let statement = parser.statement();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        match statement {
+            Some(Statement::Case(selector, arms, else_body)) => {
+                assert_eq!(render(&selector), "(n + 1)");
+                assert_eq!(arms.len(), 2);
+                let (labels, _body) = &arms[0];
+                match (&labels[0], &labels[1]) {
+                    (CaseLabel::Single(one), CaseLabel::Range(low, high)) => {
+                        assert_eq!(render(one), "1");
+                        assert_eq!(render(low), "3");
+                        assert_eq!(render(high), "5");
+                    }
+                    _ => panic!("expected a single label and a range"),
+                }
+                assert!(else_body.is_some());
+            }
+            _ => panic!("expected a case statement"),
+        }
+        assert_eq!(parser.ctt, TokenKind::Eof);
+    }
+
+    #[test]
+    fn test_constant_section() {
+        let mut parser = get_parser(
+            "const n = 10; greeting = \"hi\";
+             const half = n / 2;
+             begin",
+        );
+        let mut constants = parser.constant_section();
+        constants.append(&mut parser.constant_section());
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(parser.ctt, TokenKind::Begin);
+        let rendered: Vec<(String, String)> = constants
+            .iter()
+            .map(|constant| match constant {
+                Statement::Constant(t, value) => {
+                    (t.lexeme.clone(), render(value))
+                }
+                _ => panic!("expected a constant"),
+            })
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                (String::from("n"), String::from("10")),
+                (String::from("greeting"), String::from("\"hi\"")),
+                (String::from("half"), String::from("(n / 2)")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_section() {
+        let mut parser = get_parser(
+            "type vector = array[n] of real; name = string;
+             begin",
+        );
+        let types = parser.type_section();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(parser.ctt, TokenKind::Begin);
+        assert_eq!(types.len(), 2);
+        match &types[0] {
+            Statement::TypeDeclaration(
+                t,
+                TypeDescription::Array(element, size),
+            ) => {
+                assert_eq!(t.lexeme, "vector");
+                match element.as_ref() {
+                    TypeDescription::Simple(t) => assert_eq!(t.lexeme, "real"),
+                    _ => panic!("expected a simple element type"),
+                }
+                assert_eq!(render(size), "n");
+            }
+            _ => panic!("expected an array type declaration"),
+        }
+        match &types[1] {
+            Statement::TypeDeclaration(t, TypeDescription::Simple(alias)) => {
+                assert_eq!(t.lexeme, "name");
+                assert_eq!(alias.lexeme, "string");
+            }
+            _ => panic!("expected an alias"),
+        }
+    }
+
+    #[test]
+    fn test_record_type() {
+        let mut parser = get_parser("record x, y: integer; inner: point end;");
+        let record = parser.type_construct();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(parser.ctt, TokenKind::SemiColon);
+        match record {
+            Some(TypeDescription::Record(_t, fields)) => {
+                let names: Vec<&str> =
+                    fields.iter().map(|(id, _)| id.lexeme.as_str()).collect();
+                assert_eq!(names, vec!["x", "y", "inner"]);
+            }
+            _ => panic!("expected a record type"),
+        }
+    }
+
+    #[test]
+    fn test_field_selection() {
+        let mut parser = get_parser("a[i].b.c := p.x");
+        match parser.statement() {
+            Some(Statement::Assign(variable, value)) => {
+                let target = Expression::Variable(variable);
+                assert_eq!(render(&target), "a[i].b.c");
+                assert_eq!(render(&value), "p.x");
+            }
+            _ => panic!("expected an assignment"),
+        }
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+    }
+
+    #[test]
+    fn test_field_indexing() {
+        let mut parser = get_parser("r.a[i].b[j, k] := l.name[1]");
+        match parser.statement() {
+            Some(Statement::Assign(variable, value)) => {
+                let target = Expression::Variable(variable);
+                assert_eq!(render(&target), "r.a[i].b[j, k]");
+                assert_eq!(render(&value), "l.name[1]");
+            }
+            _ => panic!("expected an assignment"),
+        }
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+    }
+
+    #[test]
+    fn test_enumeration_type() {
+        let mut parser = get_parser("(red, green, blue);");
+        match parser.type_construct() {
+            Some(TypeDescription::Enumeration(_t, members)) => {
+                let names: Vec<&str> =
+                    members.iter().map(|id| id.lexeme.as_str()).collect();
+                assert_eq!(names, vec!["red", "green", "blue"]);
+            }
+            _ => panic!("expected an enumeration type"),
+        }
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(parser.ctt, TokenKind::SemiColon);
+        let mut parser = get_parser("();");
+        assert!(parser.type_construct().is_none());
+        assert!(!parser.errors.is_empty());
+    }
+
+    #[test]
+    fn test_subrange_type() {
+        for (text, bounds) in [("0..99;", "0..99"), ("low..-1;", "low..(- 1)")]
+        {
+            let mut parser = get_parser(text);
+            match parser.type_construct() {
+                Some(TypeDescription::Subrange(_t, low, high)) => {
+                    let rendered =
+                        format!("{}..{}", render(&low), render(&high));
+                    assert_eq!(rendered, bounds);
+                }
+                _ => panic!("expected a subrange type"),
+            }
+            assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+            assert_eq!(parser.ctt, TokenKind::SemiColon);
+        }
+    }
+
+    #[test]
+    fn test_multi_dimensional_array_type() {
+        let mut parser = get_parser("array[n, m] of array[k] of real;");
+        let mut sizes = Vec::new();
+        let mut array = parser.type_construct();
+        while let Some(TypeDescription::Array(element, size)) = array {
+            sizes.push(render(&size));
+            array = Some(*element);
+        }
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(parser.ctt, TokenKind::SemiColon);
+        assert_eq!(sizes, vec!["n", "m", "k"]);
+        match array {
+            Some(TypeDescription::Simple(t)) => assert_eq!(t.lexeme, "real"),
+            _ => panic!("expected a simple element type"),
+        }
+    }
+
+    #[test]
+    fn test_multiple_indices() {
+        assert_eq!(parse_expression("a[i, j + 1][k]"), "a[i, (j + 1), k]");
+        assert_eq!(parse_expression("a[i][j].size"), "a[i, j].size");
+    }
+
+    #[test]
+    fn test_open_array_type() {
+        let mut parser = get_parser("array of array[3] of real)");
+        match parser.type_construct() {
+            Some(TypeDescription::OpenArray(_t, element)) => match *element {
+                TypeDescription::Array(_, size) => {
+                    assert_eq!(render(&size), "3")
+                }
+                _ => panic!("expected an array element type"),
+            },
+            _ => panic!("expected an open array type"),
+        }
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(parser.ctt, TokenKind::CloseBracket);
+    }
+
+    #[test]
+    fn test_selection_from_call() {
+        assert_eq!(parse_expression("range(n).size"), "size(range(n))");
+        assert_eq!(parse_expression("f(a, 2).size + 1"), "(size(f(a, 2)) + 1)");
+        assert_eq!(parse_expression("range(5)[2]"), "range(5)[2]");
+        assert_eq!(parse_expression("grid(2)[0][1, 2]"), "grid(2)[0, 1, 2]");
+        assert_eq!(parse_expression("grid(2)[0].size"), "size(grid(2)[0])");
+    }
+
+    #[test]
+    fn test_field_selection_from_call() {
+        let mut parser = get_parser("mk(3).y");
+        assert!(parser.expression().is_none());
+        assert!(!parser.errors.is_empty());
+    }
+
+    #[test]
+    fn test_char_literals() {
+        assert_eq!(parse_expression("c <> 'a'"), "(c <> 'a')");
+        assert_eq!(parse_expression("s[i] = '\\''"), "(s[i] = '\\'')");
+    }
+
+    #[test]
+    fn test_field_widths() {
+        assert_eq!(parse_expression("f(x:8, y)"), "f(x:8, y)");
+        assert_eq!(
+            parse_expression("f(r * 2:n + 1:3)"),
+            "f((r * 2):(n + 1):3)"
+        );
+    }
 }
@@ -11,11 +11,37 @@ impl PrintVisitor {
     fn visit_type_description(&mut self, type_description: &TypeDescription) {
         match type_description {
             TypeDescription::Simple(t) => print!("{}", t.lexeme),
-            TypeDescription::Array(t, e) => {
-                print!("(Array of {}, length: ", t.lexeme);
+            TypeDescription::Array(element, e) => {
+                print!("(Array of ");
+                self.visit_type_description(element);
+                print!(", length: ");
                 self.visit_expression(e);
                 print!(")");
             }
+            TypeDescription::OpenArray(_t, element) => {
+                print!("(Array of ");
+                self.visit_type_description(element);
+                print!(")");
+            }
+            TypeDescription::Record(_t, fields) => {
+                print!("(Record ");
+                for (id, field_type) in fields {
+                    print!("{} : ", id.lexeme);
+                    self.visit_type_description(field_type);
+                    print!(",");
+                }
+                print!(")");
+            }
+            TypeDescription::Enumeration(_t, members) => {
+                let names: Vec<&str> =
+                    members.iter().map(|m| m.lexeme.as_str()).collect();
+                print!("({})", names.join(", "));
+            }
+            TypeDescription::Subrange(_t, low, high) => {
+                self.visit_expression(low);
+                print!("..");
+                self.visit_expression(high);
+            }
         }
     }
 }
@@ -23,8 +49,11 @@ impl PrintVisitor {
 impl Visitor for PrintVisitor {
     fn visit_ast(&mut self, node: &AST) {
         match node {
-            AST::Program(token, subroutines, main_block) => {
+            AST::Program(token, declarations, subroutines, main_block) => {
                 println!("Program {} ->", token.lexeme);
+                for declaration in declarations {
+                    self.visit_statement(declaration);
+                }
                 for subroutine in subroutines {
                     self.visit_subroutine(subroutine);
                 }
@@ -41,18 +70,27 @@ impl Visitor for PrintVisitor {
             Subroutine::Procedure(name, params, block) => {
                 self.visit_procedure(name, params, block)
             }
+            Subroutine::ForwardFunction(name, _params, _out_type) => {
+                println!("Forward function {};", name.lexeme)
+            }
+            Subroutine::ForwardProcedure(name, _params) => {
+                println!("Forward procedure {};", name.lexeme)
+            }
         }
     }
 
     fn visit_procedure(
         &mut self,
         name: &Token,
-        params: &Vec<(Token, TypeDescription)>,
+        params: &Vec<(Token, TypeDescription, ParameterMode)>,
         body: &Vec<Statement>,
     ) {
         print!("Procedure {}( ", name.lexeme);
         for param in params {
-            let (id, type_description) = param;
+            let (id, type_description, mode) = param;
+            if let ParameterMode::Var = mode {
+                print!("var ");
+            }
             print!("{} : ", id.lexeme);
             self.visit_type_description(type_description);
             print!(",");
@@ -64,13 +102,16 @@ impl Visitor for PrintVisitor {
     fn visit_function(
         &mut self,
         name: &Token,
-        params: &Vec<(Token, TypeDescription)>,
+        params: &Vec<(Token, TypeDescription, ParameterMode)>,
         body: &Vec<Statement>,
         out_type: &TypeDescription,
     ) {
         print!("Function {}( ", name.lexeme);
         for param in params {
-            let (id, type_description) = param;
+            let (id, type_description, mode) = param;
+            if let ParameterMode::Var = mode {
+                print!("var ");
+            }
             print!("{} : ", id.lexeme);
             self.visit_type_description(type_description);
             print!(",");
@@ -94,9 +135,26 @@ impl Visitor for PrintVisitor {
             Statement::Declaration(token, type_description) => {
                 self.visit_declaration(token, type_description)
             }
+            Statement::Constant(token, value) => {
+                print!("Constant {} = ", token.lexeme);
+                self.visit_expression(value);
+            }
+            Statement::TypeDeclaration(token, type_description) => {
+                print!("Type {} = ", token.lexeme);
+                self.visit_type_description(type_description);
+            }
             Statement::While(condition, body) => {
                 self.visit_while(condition, body)
             }
+            Statement::Repeat(body, condition) => {
+                self.visit_repeat(body, condition)
+            }
+            Statement::Case(selector, arms, else_body) => {
+                self.visit_case(selector, arms, else_body)
+            }
+            Statement::For(variable, start, direction, limit, body) => {
+                self.visit_for(variable, start, direction, limit, body)
+            }
             Statement::If(condition, body, maybe_else_body) => {
                 if let Some(else_body) = maybe_else_body {
                     self.visit_if(condition, body, Some(else_body))
@@ -110,6 +168,11 @@ impl Visitor for PrintVisitor {
             }
             Statement::Call(id, params) => self.visit_call(id, params),
             Statement::Return(token, value) => self.visit_return(token, value),
+            Statement::Subroutines(subroutines) => {
+                for subroutine in subroutines {
+                    self.visit_subroutine(subroutine);
+                }
+            }
         }
         println!();
     }
@@ -129,11 +192,18 @@ impl Visitor for PrintVisitor {
         print!("Declaration( {} : ", token.lexeme.clone());
         match type_description {
             TypeDescription::Simple(t) => print!("{}", t.lexeme.clone()),
-            TypeDescription::Array(t, e) => {
-                print!("{}[", t.lexeme.clone());
+            TypeDescription::Array(element, e) => {
+                self.visit_type_description(element);
+                print!("[");
                 self.visit_expression(&e);
                 print!("]");
             }
+            TypeDescription::Record(..)
+            | TypeDescription::Enumeration(..)
+            | TypeDescription::Subrange(..)
+            | TypeDescription::OpenArray(..) => {
+                self.visit_type_description(type_description)
+            }
         }
         print!(")");
     }
@@ -161,6 +231,61 @@ impl Visitor for PrintVisitor {
         self.visit_statement(body);
         print!("end while");
     }
+    fn visit_repeat(&mut self, body: &Vec<Statement>, condition: &Expression) {
+        println!("Repeat");
+        for statement in body {
+            self.visit_statement(statement);
+        }
+        print!("until (");
+        self.visit_expression(condition);
+        print!(")");
+    }
+    fn visit_case(
+        &mut self,
+        selector: &Expression,
+        arms: &Vec<(Vec<CaseLabel>, Statement)>,
+        else_body: &Option<Box<Statement>>,
+    ) {
+        print!("Case (");
+        self.visit_expression(selector);
+        println!(") of");
+        for (labels, body) in arms {
+            for label in labels {
+                match label {
+                    CaseLabel::Single(value) => self.visit_expression(value),
+                    CaseLabel::Range(low, high) => {
+                        self.visit_expression(low);
+                        print!("..");
+                        self.visit_expression(high);
+                    }
+                }
+                print!(",");
+            }
+            print!(": ");
+            self.visit_statement(body);
+        }
+        if let Some(body) = else_body {
+            print!("else ");
+            self.visit_statement(body);
+        }
+        print!("end case");
+    }
+    fn visit_for(
+        &mut self,
+        variable: &Token,
+        start: &Expression,
+        direction: &Token,
+        limit: &Expression,
+        body: &Statement,
+    ) {
+        print!("For ({} := ", variable.lexeme);
+        self.visit_expression(start);
+        print!(" {} ", direction.lexeme);
+        self.visit_expression(limit);
+        println!(" ) do ");
+        self.visit_statement(body);
+        print!("end for");
+    }
     fn visit_assert(&mut self, _token: &Token, condition: &Expression) {
         print!("assert(");
         self.visit_expression(condition);
@@ -194,6 +319,23 @@ impl Visitor for PrintVisitor {
                 print!(")");
             }
             Expression::Call(id, parameters) => self.visit_call(id, parameters),
+            Expression::Index(array, _id, indices) => {
+                self.visit_expression(array);
+                for index in indices {
+                    print!("[");
+                    self.visit_expression(index);
+                    print!("]");
+                }
+            }
+            Expression::Formatted(value, _colon, width, precision) => {
+                self.visit_expression(value);
+                print!(":");
+                self.visit_expression(width);
+                if let Some(precision) = precision {
+                    print!(":");
+                    self.visit_expression(precision);
+                }
+            }
         }
     }
     fn visit_literal(&mut self, token: &Token) {
@@ -202,10 +344,25 @@ impl Visitor for PrintVisitor {
     fn visit_variable(&mut self, var: &Variable) {
         match var {
             Variable::Simple(t) => print!(" {} ", t.lexeme),
-            Variable::Indexed(t, e) => {
-                print!("{}[", t.lexeme);
-                self.visit_expression(&e);
-                print!("]");
+            Variable::Indexed(t, indices) => {
+                print!("{}", t.lexeme);
+                for index in indices {
+                    print!("[");
+                    self.visit_expression(index);
+                    print!("]");
+                }
+            }
+            Variable::Field(record, field) => {
+                self.visit_variable(record);
+                print!(".{}", field.lexeme);
+            }
+            Variable::FieldIndexed(array, indices) => {
+                self.visit_variable(array);
+                for index in indices {
+                    print!("[");
+                    self.visit_expression(index);
+                    print!("]");
+                }
             }
         }
     }
@@ -1,8 +1,21 @@
 #include <assert.h>
+#include <ctype.h>
+#include <math.h>
 #include <stdio.h>
 #include <string.h>
 #include <stdlib.h>
 
+/* The compiler defines these from its options. */
+#define MP_TRUNCATE 0
+#define MP_ERROR 1
+#define MP_GROW 2
+#ifndef MP_MAX_STRING_SIZE
+#define MP_MAX_STRING_SIZE 512
+#endif
+#ifndef MP_STRING_OVERFLOW
+#define MP_STRING_OVERFLOW MP_ERROR
+#endif
+
 
 void mp_assert(int condition, char* message) {
   if (! condition) {
@@ -12,12 +25,270 @@ void mp_assert(int condition, char* message) {
   }
 }
 
-void alloc_str_array(char** str_array, int size, int string_size) {
+void mp_range_check(int value, int low, int high, int line) {
+  if (value < low || value > high) {
+    printf("Value out of range:\n");
+    printf("\t%d is not in %d..%d on line %d\n", value, low, high, line);
+    exit(1);
+  }
+}
+
+void mp_bounds_check(int index, int size, char* name, int line, int column) {
+  if (index < 0 || index >= size) {
+    printf("Index out of bounds:\n");
+    printf("\t%d is not an index of %s of size %d on line %d, column %d\n",
+           index, name, size, line, column);
+    exit(1);
+  }
+}
+
+void mp_char_check(int index, char* string, char* name, int line,
+                   int column) {
+  int length = strlen(string);
+  if (index < 1 || index > length) {
+    printf("Index out of bounds:\n");
+    printf("\t%d is not an index of %s of length %d on line %d, column %d\n",
+           index, name, length, line, column);
+    exit(1);
+  }
+}
+
+void mp_length_check(int length, int expected, int line) {
+  if (length != expected) {
+    printf("Array lengths do not match:\n");
+    printf("\t%d is not %d on line %d\n", length, expected, line);
+    exit(1);
+  }
+}
+
+/* The length a string of the given length gets when it is stored. */
+int mp_string_fit(int length, int line) {
+  if (MP_STRING_OVERFLOW == MP_GROW || length <= MP_MAX_STRING_SIZE) {
+    return length;
+  }
+  if (MP_STRING_OVERFLOW == MP_ERROR) {
+    printf("String overflow:\n");
+    printf("\t%d characters do not fit into a string of %d on line %d\n",
+           length, MP_MAX_STRING_SIZE, line);
+    exit(1);
+  }
+  return MP_MAX_STRING_SIZE;
+}
+
+void* mp_allocation_check(void* memory, int size) {
+  if (memory == NULL) {
+    printf("Out of memory:\n");
+    printf("\tcould not allocate %d bytes\n", size);
+    exit(1);
+  }
+  return memory;
+}
+
+char* mp_string_buffer(int length) {
+  if (MP_STRING_OVERFLOW == MP_GROW) {
+    return (char*) mp_allocation_check(malloc(length + 1), length + 1);
+  }
+  return (char*) mp_allocation_check(malloc(MP_MAX_STRING_SIZE + 1),
+                                     MP_MAX_STRING_SIZE + 1);
+}
+
+/* A new buffer for the value, so that the copy shares nothing with it. */
+char* mp_string_copy(char* value, int line) {
+  int length = mp_string_fit(strlen(value), line);
+  char* copy = mp_string_buffer(length);
+  memcpy(copy, value, length);
+  copy[length] = '\0';
+  return copy;
+}
+
+/* A char used as a string. */
+char* mp_char_string(char value) {
+  char* string = mp_string_buffer(1);
+  string[0] = value;
+  string[1] = '\0';
+  return string;
+}
+
+/* Stores the value into the buffer the target already has, only growing
+   strings need a bigger one. */
+char* mp_string_assign(char* target, char* value, int line) {
+  int length = mp_string_fit(strlen(value), line);
+  if (target == value) {
+    return target;
+  }
+  if (MP_STRING_OVERFLOW == MP_GROW) {
+    target = (char*) mp_allocation_check(realloc(target, length + 1),
+                                         length + 1);
+  }
+  memcpy(target, value, length);
+  target[length] = '\0';
+  return target;
+}
+
+char* mp_string_concat(char* lhs, char* rhs, int line) {
+  int lhs_length = strlen(lhs);
+  int length = mp_string_fit(lhs_length + strlen(rhs), line);
+  char* result = mp_string_buffer(length);
+  if (lhs_length > length) {
+    lhs_length = length;
+  }
+  memcpy(result, lhs, lhs_length);
+  memcpy(result + lhs_length, rhs, length - lhs_length);
+  result[length] = '\0';
+  return result;
+}
+
+/* Reads a word like the "%s" of scanf, whatever its length, into the
+   buffer of the target. A target without a buffer of its own gets a new
+   one. */
+char* mp_string_read(char* target, int line) {
+  int capacity = 16;
+  int length = 0;
+  char* word = (char*) mp_allocation_check(malloc(capacity), capacity);
+  int c = getchar();
+  while (c != EOF && isspace(c)) {
+    c = getchar();
+  }
+  while (c != EOF && !isspace(c)) {
+    if (length + 1 == capacity) {
+      capacity = capacity * 2;
+      word = (char*) mp_allocation_check(realloc(word, capacity), capacity);
+    }
+    word[length] = c;
+    length = length + 1;
+    c = getchar();
+  }
+  if (c != EOF) {
+    ungetc(c, stdin);
+  }
+  word[length] = '\0';
+  char* string;
+  if (target == NULL) {
+    string = mp_string_copy(word, line);
+  } else {
+    string = mp_string_assign(target, word, line);
+  }
+  free(word);
+  return string;
+}
+
+char* mp_boolean_name(int value) {
+  if (value) {
+    return "true";
+  }
+  return "false";
+}
+
+int mp_sqr_integer(int value) {
+  return value * value;
+}
+
+double mp_sqr_real(double value) {
+  return value * value;
+}
+
+/* The string functions count from 1 and cut their arguments to the string
+   like Turbo Pascal does. */
+int mp_pos(char* substring, char* string) {
+  if (substring[0] == '\0') {
+    return 0;
+  }
+  char* found = strstr(string, substring);
+  if (found == NULL) {
+    return 0;
+  }
+  return found - string + 1;
+}
+
+char* mp_copy(char* string, int index, int count, int line) {
+  int length = strlen(string);
+  if (index < 1) {
+    index = 1;
+  }
+  if (index > length || count < 0) {
+    count = 0;
+  } else if (count > length - index + 1) {
+    count = length - index + 1;
+  }
+  char* result = mp_string_buffer(mp_string_fit(count, line));
+  memcpy(result, string + index - 1, count);
+  result[count] = '\0';
+  return result;
+}
+
+char* mp_insert(char* source, char* string, int index, int line) {
+  int length = strlen(string);
+  int source_length = strlen(source);
+  if (index < 1) {
+    index = 1;
+  }
+  if (index > length + 1) {
+    index = length + 1;
+  }
+  char* whole = (char*) mp_allocation_check(
+      malloc(length + source_length + 1), length + source_length + 1);
+  memcpy(whole, string, index - 1);
+  memcpy(whole + index - 1, source, source_length);
+  strcpy(whole + index - 1 + source_length, string + index - 1);
+  char* result = mp_string_copy(whole, line);
+  free(whole);
+  return result;
+}
+
+char* mp_delete(char* string, int index, int count, int line) {
+  int length = strlen(string);
+  char* result = mp_string_copy(string, line);
+  if (index < 1 || index > length || count <= 0) {
+    return result;
+  }
+  if (count > length - index + 1) {
+    count = length - index + 1;
+  }
+  memmove(result + index - 1, result + index - 1 + count,
+          length - index - count + 2);
+  return result;
+}
+
+char* mp_upcase(char* string, int line) {
+  char* result = mp_string_copy(string, line);
   int i = 0;
-  while (i < size) {
-    str_array[i] = (char *) malloc(string_size);
+  while (result[i] != '\0') {
+    result[i] = toupper(result[i]);
+    i = i + 1;
+  }
+  return result;
+}
+
+char* mp_lowercase(char* string, int line) {
+  char* result = mp_string_copy(string, line);
+  int i = 0;
+  while (result[i] != '\0') {
+    result[i] = tolower(result[i]);
     i = i + 1;
   }
+  return result;
+}
+
+char* mp_trim(char* string, int line) {
+  int start = 0;
+  int end = strlen(string);
+  while (start < end && isspace(string[start])) {
+    start = start + 1;
+  }
+  while (end > start && isspace(string[end - 1])) {
+    end = end - 1;
+  }
+  return mp_copy(string, start + 1, end - start, line);
+}
+
+/* Every string in an array has a buffer of its own. */
+void mp_string_init(void* element) {
+  *(char**) element = mp_string_copy("", 0);
+}
+
+void mp_string_element_copy(void* target, void* source, int line) {
+  *(char**) target = mp_string_assign(*(char**) target, *(char**) source,
+                                      line);
 }
 
 void free_str_array(char** str_array, int size) {
@@ -28,5 +299,64 @@ void free_str_array(char** str_array, int size) {
   }
 }
 
+/* An array of many dimensions is an array of pointers to its rows. The
+   elements that hold strings are given their buffers by init. */
+void* alloc_array(int* sizes, int dimensions, int element_size,
+                  void (*init)(void*)) {
+  int i = 0;
+  if (dimensions == 1) {
+    char* array = (char*) mp_allocation_check(malloc(sizes[0] * element_size),
+                                              sizes[0] * element_size);
+    while (init != NULL && i < sizes[0]) {
+      init(array + i * element_size);
+      i = i + 1;
+    }
+    return array;
+  }
+  void** rows = (void**) mp_allocation_check(
+      malloc(sizes[0] * sizeof(void*)), sizes[0] * sizeof(void*));
+  while (i < sizes[0]) {
+    rows[i] = alloc_array(sizes + 1, dimensions - 1, element_size, init);
+    i = i + 1;
+  }
+  return rows;
+}
+
+/* The elements holding strings are copied by copy, which copies the strings
+   into the buffers of the target, so that the copies never share them. */
+void copy_array(void* target, void* source, int* target_sizes,
+                int* source_sizes, int dimensions, int element_size,
+                void (*copy)(void*, void*, int), int line) {
+  mp_length_check(source_sizes[0], target_sizes[0], line);
+  int i = 0;
+  if (dimensions == 1) {
+    if (copy == NULL) {
+      memcpy(target, source, target_sizes[0] * element_size);
+    }
+    while (copy != NULL && i < target_sizes[0]) {
+      copy((char*) target + i * element_size,
+           (char*) source + i * element_size, line);
+      i = i + 1;
+    }
+    return;
+  }
+  while (i < target_sizes[0]) {
+    copy_array(((void**) target)[i], ((void**) source)[i], target_sizes + 1,
+               source_sizes + 1, dimensions - 1, element_size, copy, line);
+    i = i + 1;
+  }
+}
+
+/* Arrays passed by value are copied by the callee, returned arrays are copied
+   to the sizes the function declares. */
+void* clone_array(void* source, int* sizes, int* source_sizes, int dimensions,
+                  int element_size, void (*init)(void*),
+                  void (*copy)(void*, void*, int), int line) {
+  void* clone = alloc_array(sizes, dimensions, element_size, init);
+  copy_array(clone, source, sizes, source_sizes, dimensions, element_size,
+             copy, line);
+  return clone;
+}
+
 int booltmp = 0;
 
@@ -269,6 +269,12 @@ fn scan_special_symbols(src: &mut Source) -> Option<Token> {
                         _ => (),
                     }
                 }
+                '.' => {
+                    lexeme.push(src.get_next_char());
+                    if src.peek() == '.' {
+                        lexeme.push(src.get_next_char());
+                    }
+                }
                 _ => {
                     lexeme.push(src.get_next_char());
                 }
@@ -325,9 +331,72 @@ fn scan_string_literal(src: &mut Source) -> Option<Token> {
     }
 }
 
+// Character literals keep the form of C character literals, so "'\''" is
+// the quote character.
+fn scan_char_literal(src: &mut Source) -> Option<Token> {
+    match src.peek() {
+        '\'' => {
+            let mut lexeme = String::from("");
+            lexeme.push(src.get_next_char());
+            let is_valid = match src.peek() {
+                '\\' => {
+                    lexeme.push(src.get_next_char());
+                    match src.peek() {
+                        '\'' | '\\' | 'n' | 't' => {
+                            lexeme.push(src.get_next_char());
+                            true
+                        }
+                        _ => false,
+                    }
+                }
+                // Pascal's doubled quote is kept in the C form.
+                '\'' => {
+                    lexeme.push(src.get_next_char());
+                    if src.peek() != '\'' {
+                        return Some(Token {
+                            lexeme,
+                            token_kind: TokenKind::Error,
+                            column: src.get_column(),
+                            row: src.get_row(),
+                        });
+                    }
+                    src.get_next_char();
+                    lexeme = String::from("'\\'");
+                    true
+                }
+                '\n' => false,
+                _ => {
+                    lexeme.push(src.get_next_char());
+                    true
+                }
+            };
+            let token_kind = match src.peek() {
+                '\'' => {
+                    lexeme.push(src.get_next_char());
+                    if is_valid {
+                        TokenKind::CharLiteral
+                    } else {
+                        TokenKind::Error
+                    }
+                }
+                _ => TokenKind::Error,
+            };
+            Some(Token {
+                lexeme,
+                token_kind,
+                column: src.get_column(),
+                row: src.get_row(),
+            })
+        }
+        _ => None,
+    }
+}
+
 fn kw_str_to_tokenkind<'a>(kw_str: &'a str) -> TokenKind {
     match kw_str {
         "var" => TokenKind::Var,
+        "const" => TokenKind::Const,
+        "type" => TokenKind::Type,
         "and" => TokenKind::And,
         "or" => TokenKind::Or,
         "if" => TokenKind::If,
@@ -336,9 +405,16 @@ fn kw_str_to_tokenkind<'a>(kw_str: &'a str) -> TokenKind {
         "of" => TokenKind::Of,
         "while" => TokenKind::While,
         "do" => TokenKind::Do,
+        "for" => TokenKind::For,
+        "to" => TokenKind::To,
+        "downto" => TokenKind::Downto,
+        "repeat" => TokenKind::Repeat,
+        "until" => TokenKind::Until,
+        "case" => TokenKind::Case,
         "begin" => TokenKind::Begin,
         "end" => TokenKind::End,
         "array" => TokenKind::Array,
+        "record" => TokenKind::Record,
         "procedure" => TokenKind::Procedure,
         "function" => TokenKind::Function,
         "program" => TokenKind::Program,
@@ -367,6 +443,7 @@ fn special_symbol_to_tokenkind<'a>(kw_str: &'a str) -> TokenKind {
         "[" => TokenKind::OpenSquareBracket,
         "]" => TokenKind::CloseSquareBracket,
         "." => TokenKind::Dot,
+        ".." => TokenKind::DoubleDot,
         "," => TokenKind::Comma,
         ":" => TokenKind::Colon,
         ";" => TokenKind::SemiColon,
@@ -382,6 +459,7 @@ pub fn build_scanner(source: Source) -> Scanner {
     scanfunctions.push(scan_colon_or_assign);
     scanfunctions.push(scan_special_symbols);
     scanfunctions.push(scan_string_literal);
+    scanfunctions.push(scan_char_literal);
     Scanner {
         scanfunctions: scanfunctions,
         src: source,
@@ -441,6 +519,28 @@ mod test {
         assert_eq!("\"lit string\"", t.lexeme.as_str());
     }
 
+    #[test]
+    fn test_scan_char_literal() {
+        let text = String::from("'A' '\\'' '\\n' '''' '' 'ab'");
+        let mut scanner = get_scanner(text);
+        let t = scanner.get_next_token();
+        assert!(variant_eq(&TokenKind::CharLiteral, &t.token_kind));
+        assert_eq!("'A'", t.lexeme.as_str());
+        let t = scanner.get_next_token();
+        assert!(variant_eq(&TokenKind::CharLiteral, &t.token_kind));
+        assert_eq!("'\\''", t.lexeme.as_str());
+        let t = scanner.get_next_token();
+        assert!(variant_eq(&TokenKind::CharLiteral, &t.token_kind));
+        assert_eq!("'\\n'", t.lexeme.as_str());
+        let t = scanner.get_next_token();
+        assert!(variant_eq(&TokenKind::CharLiteral, &t.token_kind));
+        assert_eq!("'\\''", t.lexeme.as_str());
+        let t = scanner.get_next_token();
+        assert!(variant_eq(&TokenKind::Error, &t.token_kind));
+        let t = scanner.get_next_token();
+        assert!(variant_eq(&TokenKind::Error, &t.token_kind));
+    }
+
     #[test]
     fn test_scan_declaration_stmnt() {
         let text = String::from("var MAXIMIUM_POWER_999: integer := 99999;\n");
@@ -488,4 +588,20 @@ mod test {
         assert_eq!("<>", t.lexeme.as_str());
     }
 
+    #[test]
+    fn test_scan_range() {
+        let text = String::from("1..5");
+        let mut scanner = get_scanner(text);
+        let ok_tokens = vec![
+            (TokenKind::IntegerLiteral, "1"),
+            (TokenKind::DoubleDot, ".."),
+            (TokenKind::IntegerLiteral, "5"),
+        ];
+        for ok in ok_tokens {
+            let token = scanner.get_next_token();
+            assert!(variant_eq(&ok.0, &token.token_kind));
+            assert_eq!(ok.1, token.lexeme.as_str());
+        }
+    }
+
 }
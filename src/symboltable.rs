@@ -2,12 +2,16 @@ use crate::typedast::*;
 use crate::address::Address;
 use std::collections::HashMap;
 
+// Scope 0 holds the predefined identifiers and encloses the global scope.
+const GLOBAL_SCOPE_NUMBER: i32 = 1;
+
 #[derive(PartialEq)]
 pub enum ConstructCategory {
     SimpleVar,
     ArrayVar,
-    Function(Vec<NodeType>, NodeType),
-    Procedure(Vec<NodeType>),
+    Constant,
+    Function(Vec<ParameterType>, NodeType),
+    Procedure(Vec<ParameterType>),
     TypeId,
     Special,
 }
@@ -118,6 +122,18 @@ impl Symboltable {
         }
     }
 
+    // A name that can not be called does not hide the predefined subroutine
+    // of the same name, so "a.size" works with an enumeration member Size.
+    pub fn lookup_subroutine(&self, name: &String) -> Option<&Entry> {
+        let entry = self.lookup(name)?;
+        match entry.category {
+            ConstructCategory::Function(..)
+            | ConstructCategory::Procedure(_)
+            | ConstructCategory::Special => Some(entry),
+            _ => self.lookup_explicit_scope(name, 0).or(Some(entry)),
+        }
+    }
+
     fn lookup_explicit_scope(&self, name: &String, scope_number: i32) -> Option<&Entry> {
         match self.table.get(&scope_number) {
             Some(scope) => match scope.get(name) {
@@ -137,6 +153,30 @@ impl Symboltable {
             None => false,
         }
     }
+
+    // Variables of the current scope and the ones enclosing it, the global
+    // scope excluded. Shadowed variables are included as well.
+    pub fn enclosing_variables(&self) -> Vec<&Entry> {
+        let mut variables = Vec::new();
+        let mut scope_number = self.current_scope_number;
+        while scope_number > GLOBAL_SCOPE_NUMBER {
+            if let Some(scope) = self.table.get(&scope_number) {
+                for entry in scope.values() {
+                    match entry.category {
+                        ConstructCategory::SimpleVar
+                        | ConstructCategory::ArrayVar => variables.push(entry),
+                        _ => (),
+                    }
+                }
+            }
+            match self.scope_information_table.get(&scope_number) {
+                Some(scope) => scope_number = scope.enclosing_scope_number,
+                None => break,
+            }
+        }
+        variables.sort_by_key(|entry| entry.address.as_u64());
+        variables
+    }
 }
 
 fn predefined_ids() -> Vec<Entry> {
@@ -173,9 +213,17 @@ fn predefined_ids() -> Vec<Entry> {
         scope_number: 0,
         address: Address::new_simple(0),
     });
+    entries.push(Entry {
+        name: String::from("char"),
+        category: ConstructCategory::TypeId,
+        value: String::from("0"),
+        entry_type: NodeType::Simple(SimpleType::Char),
+        scope_number: 0,
+        address: Address::new_simple(0),
+    });
     entries.push(Entry {
         name: String::from("false"),
-        category: ConstructCategory::SimpleVar,
+        category: ConstructCategory::Constant,
         value: String::from("0"),
         entry_type: NodeType::Simple(SimpleType::Boolean),
         scope_number: 0,
@@ -183,7 +231,7 @@ fn predefined_ids() -> Vec<Entry> {
     });
     entries.push(Entry {
         name: String::from("true"),
-        category: ConstructCategory::SimpleVar,
+        category: ConstructCategory::Constant,
         value: String::from("1"),
         entry_type: NodeType::Simple(SimpleType::Boolean),
         scope_number: 0,
@@ -197,6 +245,14 @@ fn predefined_ids() -> Vec<Entry> {
         scope_number: 0,
         address: Address::new_simple(0),
     });
+    entries.push(Entry {
+        name: String::from("write"),
+        category: ConstructCategory::Special,
+        value: String::from(""),
+        entry_type: NodeType::Simple(SimpleType::String),
+        scope_number: 0,
+        address: Address::new_simple(0),
+    });
     entries.push(Entry {
         name: String::from("read"),
         category: ConstructCategory::Special,
@@ -205,6 +261,38 @@ fn predefined_ids() -> Vec<Entry> {
         scope_number: 0,
         address: Address::new_simple(0),
     });
+    entries.push(Entry {
+        name: String::from("ord"),
+        category: ConstructCategory::Special,
+        value: String::from(""),
+        entry_type: NodeType::Simple(SimpleType::Integer),
+        scope_number: 0,
+        address: Address::new_simple(0),
+    });
+    entries.push(Entry {
+        name: String::from("chr"),
+        category: ConstructCategory::Special,
+        value: String::from(""),
+        entry_type: NodeType::Simple(SimpleType::Char),
+        scope_number: 0,
+        address: Address::new_simple(0),
+    });
+    entries.push(Entry {
+        name: String::from("succ"),
+        category: ConstructCategory::Special,
+        value: String::from(""),
+        entry_type: NodeType::Simple(SimpleType::Integer),
+        scope_number: 0,
+        address: Address::new_simple(0),
+    });
+    entries.push(Entry {
+        name: String::from("pred"),
+        category: ConstructCategory::Special,
+        value: String::from(""),
+        entry_type: NodeType::Simple(SimpleType::Integer),
+        scope_number: 0,
+        address: Address::new_simple(0),
+    });
     entries.push(Entry {
         name: String::from("size"),
         category: ConstructCategory::Special,
@@ -213,6 +301,39 @@ fn predefined_ids() -> Vec<Entry> {
         address: Address::new_simple(0),
         scope_number: 0,
     });
+    let string_subroutines = vec![
+        ("length", SimpleType::Integer),
+        ("copy", SimpleType::String),
+        ("pos", SimpleType::Integer),
+        ("insert", SimpleType::String),
+        ("delete", SimpleType::String),
+        ("upcase", SimpleType::String),
+        ("lowercase", SimpleType::String),
+        ("trim", SimpleType::String),
+    ];
+    let math_functions = vec![
+        ("abs", SimpleType::Integer),
+        ("sqr", SimpleType::Integer),
+        ("sqrt", SimpleType::Real),
+        ("sin", SimpleType::Real),
+        ("cos", SimpleType::Real),
+        ("exp", SimpleType::Real),
+        ("ln", SimpleType::Real),
+        ("trunc", SimpleType::Integer),
+        ("round", SimpleType::Integer),
+    ];
+    for (name, simple_type) in
+        string_subroutines.into_iter().chain(math_functions)
+    {
+        entries.push(Entry {
+            name: String::from(name),
+            category: ConstructCategory::Special,
+            value: String::from(""),
+            entry_type: NodeType::Simple(simple_type),
+            address: Address::new_simple(0),
+            scope_number: 0,
+        });
+    }
 
     entries
 }
@@ -231,11 +352,15 @@ pub fn get_symbol_table() -> Symboltable {
     let mut scope_table: HashMap<i32, Scope> = HashMap::new();
     table.insert(0, scope_zero);
     scope_table.insert(0, scope_zero_info);
-    Symboltable {
+    let mut st = Symboltable {
         scope_information_table: scope_table,
         table,
         scopestack: vec![0],
         current_scope_number: 0,
         generator_no: 1,
-    }
+    };
+    // The program gets a scope of its own, so its declarations can shadow
+    // the predefined identifiers.
+    st.new_scope_in_current_scope(false);
+    st
 }
@@ -3,11 +3,14 @@ use std::fmt;
 pub enum TokenKind {
     Identifier,
     StringLiteral,
+    CharLiteral,
     IntegerLiteral,
     RealLiteral,
     Eof,
     Error,
     Var,
+    Const,
+    Type,
     And,
     Or,
     Not,
@@ -17,9 +20,16 @@ pub enum TokenKind {
     Of,
     While,
     Do,
+    For,
+    To,
+    Downto,
+    Repeat,
+    Until,
+    Case,
     Begin,
     End,
     Array,
+    Record,
     Procedure,
     Function,
     Program,
@@ -41,6 +51,7 @@ pub enum TokenKind {
     OpenSquareBracket,
     CloseSquareBracket,
     Dot,
+    DoubleDot,
     Colon,
     Comma,
     SemiColon,
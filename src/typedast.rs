@@ -1,5 +1,6 @@
 use crate::token::Token;
 use crate::address::Address;
+use crate::ast::ParameterMode;
 use crate::opkind::OpKind;
 
 #[derive(PartialEq, Clone, Debug)]
@@ -8,37 +9,67 @@ pub enum SimpleType {
     String,
     Real,
     Integer,
+    Char,
 }
 
 #[derive(PartialEq, Clone, Debug)]
 pub enum NodeType {
     Simple(SimpleType),
-    ArrayOf(SimpleType),
+    // Arrays of a declared type carry its name, so that two array types
+    // are the same only when their names are.
+    ArrayOf(Box<NodeType>, Option<String>),
+    // Every record type has its own number, the fields are kept in order.
+    // An array field has the sizes of its dimensions with it.
+    Record(u64, Vec<(String, NodeType, Vec<i64>)>),
+    // Members are represented by their ordinals, the names are for printing.
+    Enumeration(u64, Vec<String>),
+    // Integers within inclusive bounds. Only variables have this type, their
+    // values are plain Integers.
+    Subrange(i32, i32),
 }
 
 pub enum TypedAST {
-    Program(Token, Vec<TypedSubroutine>, Vec<TypedStatement>),
+    Program(
+        Token,
+        Vec<NodeType>,
+        Vec<TypedSubroutine>,
+        Vec<TypedStatement>,
+    ),
+}
+
+// How a subroutine takes an argument. An open array takes arrays of any
+// length, the lengths are passed along as hidden arguments. The lengths of
+// an array parameter are known when its sizes are constants.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ParameterType {
+    pub node_type: NodeType,
+    pub mode: ParameterMode,
+    pub open_array: bool,
+    pub lengths: Vec<Option<i64>>,
 }
 
+#[derive(Clone)]
 pub enum TypedTypeDescription {
     Simple(NodeType),
-    Array(NodeType, TypedExpression),
+    // The sizes of the dimensions, outermost first.
+    Array(NodeType, Vec<TypedExpression>),
 }
 
 pub enum TypedSubroutine {
     Function(
         Address,
-        Vec<(TypedVariable, TypedTypeDescription)>,
+        Vec<(TypedVariable, TypedTypeDescription, ParameterMode)>,
         Vec<TypedStatement>,
         TypedTypeDescription,
     ),
     Procedure(
         Address,
-        Vec<(TypedVariable, TypedTypeDescription)>,
+        Vec<(TypedVariable, TypedTypeDescription, ParameterMode)>,
         Vec<TypedStatement>,
     ),
 }
 
+#[derive(Clone)]
 pub struct TypedVariable {
     pub token: Token,
     pub address: Address,
@@ -46,9 +77,18 @@ pub struct TypedVariable {
     pub substructure: TypedVariableStructure,
 }
 
+#[derive(Clone)]
 pub enum TypedVariableStructure {
     Simple,
-    Indexed(TypedExpression),
+    // One index for each dimension that is indexed.
+    Indexed(Vec<TypedExpression>),
+    Field(Box<TypedVariable>),
+    // An element of an array that is not a plain variable, like the array
+    // field "r.a[i]". Every index is an element of its own.
+    Element(Box<TypedVariable>, Box<TypedExpression>),
+    // A character of a string, counting from 1. The address holds the
+    // index counting from 0.
+    Character(Box<TypedVariable>, Box<TypedExpression>, Address),
 }
 
 pub enum TypedStatement {
@@ -65,9 +105,38 @@ pub enum TypedStatement {
     Read(Vec<Box<TypedVariable>>),
     Return(Token, Option<TypedExpression>),
     While(TypedExpression, Box<TypedStatement>),
-    Write(Vec<TypedExpression>),
+    Repeat(Vec<TypedStatement>, TypedExpression),
+    Case(
+        TypedExpression,
+        Vec<(Vec<TypedCaseLabel>, TypedStatement)>,
+        Option<Box<TypedStatement>>,
+    ),
+    For(
+        TypedVariable,
+        Box<TypedExpression>,
+        Token,
+        Box<TypedExpression>,
+        Address,
+        Box<TypedStatement>,
+    ),
+    // The flag tells whether a newline follows the arguments.
+    Write(Vec<TypedWriteArgument>, bool),
+}
+
+pub struct TypedWriteArgument {
+    pub value: TypedExpression,
+    pub width: Option<TypedExpression>,
+    pub precision: Option<TypedExpression>,
+}
+
+// Integer labels are inclusive ranges, a single value is a range of one.
+#[derive(Clone)]
+pub enum TypedCaseLabel {
+    Range(i64, i64),
+    String(String),
 }
 
+#[derive(Clone)]
 pub struct TypedExpression {
     pub token: Token,
     pub address: Address,
@@ -75,11 +144,24 @@ pub struct TypedExpression {
     pub substructure: TypedExpressionStructure,
 }
 
+#[derive(Clone)]
 pub enum TypedExpressionStructure {
     Binary(OpKind, Box<TypedExpression>, Box<TypedExpression>),
     Call(Address, Vec<TypedExpression>),
+    Conversion(Box<TypedExpression>),
+    // An element of the array a call returns, the expression has the
+    // indexed address.
+    Indexed(Box<TypedExpression>, Vec<TypedExpression>),
     Literal,
-    Size(Address),
+    // An Integer that must be within the bounds of the Subrange type of the
+    // expression. It has the address of the checked expression.
+    RangeCheck(Box<TypedExpression>),
+    Reference(Box<TypedVariable>),
+    // A call to a function of the runtime or the C library, by its C name.
+    RuntimeCall(String, Vec<TypedExpression>),
+    Size(Box<TypedExpression>),
+    // The successor or the predecessor of an ordinal, "succ" and "pred".
+    Step(Box<TypedExpression>, i32),
     Unary(Box<TypedExpression>),
     Variable(Box<TypedVariable>),
 }
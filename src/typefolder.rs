@@ -5,28 +5,129 @@ use crate::opkind::*;
 use crate::token::Token;
 use crate::token::TokenKind;
 use crate::typedast::*;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 
 
-fn type_id_to_simple_type(identifier: &String) -> Option<SimpleType> {
-    match identifier.as_str() {
-        "string" => Some(SimpleType::String),
-        "integer" => Some(SimpleType::Integer),
-        "boolean" => Some(SimpleType::Boolean),
-        "real" => Some(SimpleType::Real),
-        _ => None,
+// Subrange variables hold Integer values.
+fn value_type(node_type: &NodeType) -> NodeType {
+    match node_type {
+        NodeType::Subrange(..) => NodeType::Simple(SimpleType::Integer),
+        _ => node_type.clone(),
     }
 }
 
-fn node_type_to_simple_type(node_type: &NodeType) -> SimpleType {
+// The type of the values write prints, the elements for arrays.
+fn written_type(node_type: &NodeType) -> &NodeType {
     match node_type {
-        NodeType::ArrayOf(t) => t.clone(),
-        NodeType::Simple(t) => t.clone(),
+        NodeType::ArrayOf(element, _) => written_type(element),
+        _ => node_type,
+    }
+}
+
+// The length an array size expression has at compile time.
+fn constant_length(size: &TypedExpression) -> Option<i64> {
+    match size.substructure {
+        TypedExpressionStructure::Literal => size.token.lexeme.parse().ok(),
+        _ => None,
+    }
+}
+
+fn case_labels_overlap(a: &TypedCaseLabel, b: &TypedCaseLabel) -> bool {
+    match (a, b) {
+        (
+            TypedCaseLabel::Range(a_low, a_high),
+            TypedCaseLabel::Range(b_low, b_high),
+        ) => a_low <= b_high && b_low <= a_high,
+        (TypedCaseLabel::String(a), TypedCaseLabel::String(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn boolean_value(value: bool) -> String {
+    String::from(if value { "1" } else { "0" })
+}
+
+// The code of a character literal, which is written as in C.
+fn char_code(lexeme: &str) -> Option<i64> {
+    let characters: Vec<char> = lexeme.chars().collect();
+    match characters.as_slice() {
+        ['\'', '\\', 'n', '\''] => Some(10),
+        ['\'', '\\', 't', '\''] => Some(9),
+        ['\'', '\\', c, '\''] if *c == '\'' || *c == '\\' => Some(*c as i64),
+        ['\'', '\\', digits @ .., '\''] => {
+            let digits: String = digits.iter().collect();
+            i64::from_str_radix(&digits, 8).ok()
+        }
+        ['\'', c, '\''] => Some(*c as i64),
+        _ => None,
     }
 }
 
+// The characters that need no escaping are written as they are, the rest
+// as octal escapes.
+fn char_literal(code: i64) -> String {
+    match code as u8 as char {
+        c if c.is_ascii_graphic() && c != '\'' && c != '\\' || c == ' ' => {
+            format!("'{}'", c)
+        }
+        _ => format!("'\\{:o}'", code),
+    }
+}
+
+fn compare<T: PartialOrd>(a: T, b: T, relation: &Relational) -> String {
+    boolean_value(match relation {
+        Relational::Equal => a == b,
+        Relational::NotEqual => a != b,
+        Relational::Smaller => a < b,
+        Relational::SmallerE => a <= b,
+        Relational::Larger => a > b,
+        Relational::LargerE => a >= b,
+    })
+}
+
+// Nested subroutines reach the enclosing variables through pointers. Arrays
+// are pointers already, so they are handed over as such with their size.
+fn captured_variable(variable: &TypedVariable) -> TypedVariable {
+    let number = variable.address.as_u64();
+    let address = match variable.node_type {
+        NodeType::ArrayOf(..) => Address::new_simple(number),
+        NodeType::Simple(_)
+        | NodeType::Record(..)
+        | NodeType::Enumeration(..)
+        | NodeType::Subrange(..) => Address::new_reference(number),
+    };
+    TypedVariable {
+        token: variable.token.clone(),
+        address,
+        node_type: variable.node_type.clone(),
+        substructure: TypedVariableStructure::Simple,
+    }
+}
+
+struct SubroutineHeading {
+    address: Address,
+    scope_number: i32,
+    parameters: Vec<(TypedVariable, TypedTypeDescription, ParameterMode)>,
+    environment: Vec<TypedVariable>,
+    out_type: Option<TypedTypeDescription>,
+}
+
 pub struct TypeFolder {
     errors: Vec<String>,
     address_generator_no: u64,
+    // The variables each nested subroutine captures, keyed by its address.
+    environments: HashMap<u64, Vec<TypedVariable>>,
+    // The captured variables of the subroutines being folded, innermost last.
+    frames: Vec<Vec<TypedVariable>>,
+    // The result types of the subroutines being folded, innermost last.
+    result_types: Vec<Option<NodeType>>,
+    nested_subroutines: Vec<TypedSubroutine>,
+    // Control variables of the for loops being folded.
+    loop_variables: Vec<Address>,
+    // Record and enumeration types in the order they were declared in,
+    // indexed by number.
+    type_definitions: Vec<NodeType>,
 }
 
 impl TypeFolder {
@@ -34,6 +135,12 @@ impl TypeFolder {
         TypeFolder {
             errors: Vec::new(),
             address_generator_no: 2, // 0 is true, 1 is false so we go from 2
+            environments: HashMap::new(),
+            frames: Vec::new(),
+            result_types: Vec::new(),
+            nested_subroutines: Vec::new(),
+            loop_variables: Vec::new(),
+            type_definitions: Vec::new(),
         }
     }
 
@@ -46,6 +153,11 @@ impl TypeFolder {
         Address::new_simple(self.address_generator_no)
     }
 
+    fn get_new_reference_address(&mut self) -> Address {
+        self.address_generator_no = self.address_generator_no + 1;
+        Address::new_reference(self.address_generator_no)
+    }
+
     fn get_new_indexed_address(
         &mut self,
         address: Address,
@@ -70,18 +182,19 @@ impl TypeFolder {
         st: &mut Symboltable,
     ) -> Option<TypedAST> {
         match node {
-            AST::Program(token, subroutines, main_block) => {
-                let mut typed_subroutines = Vec::new();
-                for subroutine in subroutines {
-                    if let Some(sr) = self.fold_subroutine(subroutine, st) {
-                        typed_subroutines.push(sr);
-                    }
+            AST::Program(token, declarations, subroutines, main_block) => {
+                for declaration in declarations {
+                    self.fold_statement(declaration, st);
                 }
+                let mut typed_subroutines =
+                    self.fold_subroutines(subroutines, st);
                 if let TypedStatement::Block(block) =
                     self.fold_block(main_block, st)
                 {
+                    typed_subroutines.append(&mut self.nested_subroutines);
                     Some(TypedAST::Program(
                         token.clone(),
+                        self.type_definitions.clone(),
                         typed_subroutines,
                         block,
                     ))
@@ -92,113 +205,325 @@ impl TypeFolder {
         }
     }
 
-    fn fold_subroutine(
+    // All headings are entered into the symboltable before any body is
+    // folded, so a subroutine can call the ones declared after it.
+    fn fold_subroutines(
+        &mut self,
+        subroutines: &Vec<Subroutine>,
+        st: &mut Symboltable,
+    ) -> Vec<TypedSubroutine> {
+        let mut headings = Vec::new();
+        let mut forward_declarations = Vec::new();
+        for subroutine in subroutines {
+            headings.push(self.fold_heading(
+                subroutine,
+                &mut forward_declarations,
+                st,
+            ));
+        }
+        for token in &forward_declarations {
+            self.handle_error(
+                token,
+                "Forward declared subroutine is never defined",
+            );
+        }
+        let mut typed_subroutines = Vec::new();
+        for (subroutine, heading) in subroutines.iter().zip(headings) {
+            if let Some(heading) = heading {
+                if let Some(sr) = self.fold_subroutine(subroutine, heading, st)
+                {
+                    typed_subroutines.push(sr);
+                }
+            }
+        }
+        typed_subroutines
+    }
+
+    // Returns the heading only for subroutines that have a body to fold.
+    fn fold_heading(
         &mut self,
         node: &Subroutine,
+        forward_declarations: &mut Vec<Token>,
         st: &mut Symboltable,
-    ) -> Option<TypedSubroutine> {
-        match node {
-            Subroutine::Procedure(token, params, body) => {
-                self.fold_procedure(token, params, body, st)
+    ) -> Option<SubroutineHeading> {
+        let (token, params, out_type, is_forward) = match node {
+            Subroutine::Function(token, params, _body, out_type) => {
+                (token, params, Some(out_type), false)
+            }
+            Subroutine::Procedure(token, params, _body) => {
+                (token, params, None, false)
             }
-            Subroutine::Function(token, params, body, out_type) => {
-                self.fold_function(token, params, body, out_type, st)
+            Subroutine::ForwardFunction(token, params, out_type) => {
+                (token, params, Some(out_type), true)
             }
+            Subroutine::ForwardProcedure(token, params) => {
+                (token, params, None, true)
+            }
+        };
+        let environment = self.fold_environment(token, st);
+        let scope_number = st.new_scope_in_current_scope(false);
+        let typed_params = self.fold_parameters(params, st);
+        let typed_out_type =
+            out_type.map(|t| self.fold_type_description(t, st));
+        st.exit_scope();
+        let mut parameters = typed_params?;
+        let category = match &typed_out_type {
+            Some(Some(typed_type)) => {
+                let node_type = match typed_type {
+                    TypedTypeDescription::Simple(t) => t,
+                    TypedTypeDescription::Array(t, _e) => t,
+                };
+                ConstructCategory::Function(
+                    self.get_node_type_for_parameters(&parameters),
+                    node_type.clone(),
+                )
+            }
+            Some(None) => {
+                self.handle_error(token, "Unknown return type");
+                return None;
+            }
+            None => ConstructCategory::Procedure(
+                self.get_node_type_for_parameters(&parameters),
+            ),
+        };
+        let address = self.declare_subroutine(
+            token,
+            category,
+            is_forward,
+            forward_declarations,
+            st,
+        )?;
+        self.environments
+            .insert(address.as_u64(), environment.clone());
+        if is_forward {
+            return None;
+        }
+        let environment: Vec<TypedVariable> =
+            environment.iter().map(captured_variable).collect();
+        for variable in &environment {
+            parameters.push((
+                variable.clone(),
+                TypedTypeDescription::Simple(variable.node_type.clone()),
+                ParameterMode::Var,
+            ));
         }
+        Some(SubroutineHeading {
+            address,
+            scope_number,
+            parameters,
+            environment,
+            out_type: typed_out_type.flatten(),
+        })
     }
 
-    fn fold_function(
+    // A nested subroutine captures all variables of the subroutines
+    // enclosing it. The shadowed ones are included too, as the subroutines
+    // it calls may need them.
+    fn fold_environment(
         &mut self,
         token: &Token,
-        params: &Vec<(Token, TypeDescription)>,
-        body: &Vec<Statement>,
-        out_type: &TypeDescription,
-        st: &mut Symboltable,
-    ) -> Option<TypedSubroutine> {
-        let upper_scope_no = st.get_current_scope_number();
-        let address = self.get_new_simple_address();
-        st.new_scope_in_current_scope(false);
-        if let Some(typed_type) = self.fold_type_description(out_type, st) {
-            let node_type = match &typed_type {
-                TypedTypeDescription::Simple(t) => t,
-                TypedTypeDescription::Array(t, _e) => t,
-            };
-            if let Some(typed_params) = self.fold_parameters(params, st) {
-                let entry = Entry {
-                    name: token.lexeme.clone(),
-                    value: String::new(),
-                    scope_number: upper_scope_no,
-                    entry_type: NodeType::Simple(SimpleType::Boolean),
+        st: &Symboltable,
+    ) -> Vec<TypedVariable> {
+        let mut environment = Vec::new();
+        for entry in st.enclosing_variables() {
+            environment.push(TypedVariable {
+                token: Token {
+                    token_kind: TokenKind::Identifier,
+                    lexeme: entry.name.clone(),
+                    row: token.row,
+                    column: token.column,
+                },
+                address: entry.address.clone(),
+                node_type: entry.entry_type.clone(),
+                substructure: TypedVariableStructure::Simple,
+            });
+        }
+        environment
+    }
+
+    // Inside a nested subroutine the captured variables are reached through
+    // its hidden parameters.
+    fn frame_address(&self, address: &Address) -> Address {
+        if let Some(frame) = self.frames.last() {
+            for variable in frame {
+                if variable.address.as_u64() == address.as_u64() {
+                    return variable.address.clone();
+                }
+            }
+        }
+        address.clone()
+    }
+
+    // The hidden arguments of a call, as seen from the calling subroutine.
+    fn environment_arguments(
+        &self,
+        token: &Token,
+        subroutine: &Address,
+    ) -> Vec<TypedExpression> {
+        let mut arguments = Vec::new();
+        if let Some(environment) = self.environments.get(&subroutine.as_u64()) {
+            for variable in environment {
+                let address = self.frame_address(&variable.address);
+                arguments.push(TypedExpression {
+                    token: token.clone(),
                     address: address.clone(),
-                    category: ConstructCategory::Function(
-                        self.get_node_type_for_parameters(&typed_params),
-                        node_type.clone(),
-                    ),
-                };
-                st.add_entry(entry);
-                let typed_body_stmnt = self.fold_block(body, st);
-                if self
-                    .validate_return_for_function(&node_type, &typed_body_stmnt)
-                {
-                    if let TypedStatement::Block(typed_body) =
-                        self.fold_block(body, st)
-                    {
-                        st.exit_scope();
-                        return Some(TypedSubroutine::Function(
+                    node_type: variable.node_type.clone(),
+                    substructure: TypedExpressionStructure::Reference(
+                        Box::from(TypedVariable {
+                            token: variable.token.clone(),
                             address,
-                            typed_params,
-                            typed_body,
-                            typed_type,
-                        ));
-                    }
+                            node_type: variable.node_type.clone(),
+                            substructure: TypedVariableStructure::Simple,
+                        }),
+                    ),
+                });
+            }
+        }
+        arguments
+    }
+
+    fn declare_subroutine(
+        &mut self,
+        token: &Token,
+        category: ConstructCategory,
+        is_forward: bool,
+        forward_declarations: &mut Vec<Token>,
+        st: &mut Symboltable,
+    ) -> Option<Address> {
+        if let Some(entry) = st.lookup(&token.lexeme) {
+            let is_subroutine = matches!(
+                entry.category,
+                ConstructCategory::Function(_, _)
+                    | ConstructCategory::Procedure(_)
+            );
+            if is_subroutine && st.in_current_scope(&token.lexeme) {
+                let pending = forward_declarations
+                    .iter()
+                    .position(|t| t.lexeme == token.lexeme);
+                if let (Some(index), false) = (pending, is_forward) {
+                    forward_declarations.remove(index);
                 } else {
+                    self.handle_error(token, "Subroutine is declared twice");
+                    return None;
+                }
+                if entry.category != category {
                     self.handle_error(
                         token,
-                        "No return statement of correct type found in function",
+                        "Heading does not match the forward declaration",
                     );
+                    return None;
                 }
+                return Some(entry.address.clone());
+            }
+        }
+        let address = self.get_new_simple_address();
+        let entry = Entry {
+            name: token.lexeme.clone(),
+            value: String::new(),
+            scope_number: st.get_current_scope_number(),
+            entry_type: NodeType::Simple(SimpleType::Boolean),
+            address: address.clone(),
+            category,
+        };
+        st.add_entry(entry);
+        if is_forward {
+            forward_declarations.push(token.clone());
+        }
+        Some(address)
+    }
+
+    fn fold_subroutine(
+        &mut self,
+        node: &Subroutine,
+        heading: SubroutineHeading,
+        st: &mut Symboltable,
+    ) -> Option<TypedSubroutine> {
+        match node {
+            Subroutine::Procedure(_token, _params, body) => {
+                Some(self.fold_procedure(heading, body, st))
             }
+            Subroutine::Function(token, _params, body, _out_type) => {
+                self.fold_function(token, heading, body, st)
+            }
+            _ => None,
         }
+    }
+
+    // The body is folded in the scope where the parameters were declared.
+    fn fold_subroutine_body(
+        &mut self,
+        heading: &SubroutineHeading,
+        body: &Vec<Statement>,
+        st: &mut Symboltable,
+    ) -> Vec<TypedStatement> {
+        st.enter_scope(Scope {
+            scope_number: heading.scope_number,
+            enclosing_scope_number: st.get_current_scope_number(),
+            is_closed: false,
+        });
+        self.frames.push(heading.environment.clone());
+        self.result_types.push(match &heading.out_type {
+            Some(TypedTypeDescription::Simple(node_type)) => {
+                Some(node_type.clone())
+            }
+            _ => None,
+        });
+        let typed_body = self.fold_block(body, st);
+        self.result_types.pop();
+        self.frames.pop();
         st.exit_scope();
-        return None;
+        match typed_body {
+            TypedStatement::Block(statements) => statements,
+            statement => vec![statement],
+        }
     }
 
-    fn fold_procedure(
+    fn fold_function(
         &mut self,
         token: &Token,
-        params: &Vec<(Token, TypeDescription)>,
+        heading: SubroutineHeading,
         body: &Vec<Statement>,
         st: &mut Symboltable,
     ) -> Option<TypedSubroutine> {
-        let upper_scope_no = st.get_current_scope_number();
-        let address = self.get_new_simple_address();
-        st.new_scope_in_current_scope(false);
-        if let Some(typed_params) = self.fold_parameters(params, st) {
-            let entry = Entry {
-                name: token.lexeme.clone(),
-                value: String::new(),
-                scope_number: upper_scope_no,
-                entry_type: NodeType::Simple(SimpleType::Boolean),
-                address: address.clone(),
-                category: ConstructCategory::Procedure(
-                    self.get_node_type_for_parameters(&typed_params),
-                ),
-            };
-            st.add_entry(entry);
-            if let TypedStatement::Block(typed_body) = self.fold_block(body, st)
-            {
-                st.exit_scope();
-                return Some(TypedSubroutine::Procedure(
-                    address,
-                    typed_params,
+        let typed_type = heading.out_type.clone()?;
+        let node_type = match &typed_type {
+            TypedTypeDescription::Simple(t) => t,
+            TypedTypeDescription::Array(t, _e) => t,
+        };
+        let typed_body = self.fold_subroutine_body(&heading, body, st);
+        let typed_body_stmnt = TypedStatement::Block(typed_body);
+        if self.validate_return_for_function(&node_type, &typed_body_stmnt) {
+            if let TypedStatement::Block(typed_body) = typed_body_stmnt {
+                return Some(TypedSubroutine::Function(
+                    heading.address,
+                    heading.parameters,
                     typed_body,
+                    typed_type,
                 ));
             }
+        } else {
+            self.handle_error(
+                token,
+                "No return statement of correct type found in function",
+            );
         }
-        st.exit_scope();
         return None;
     }
 
+    fn fold_procedure(
+        &mut self,
+        heading: SubroutineHeading,
+        body: &Vec<Statement>,
+        st: &mut Symboltable,
+    ) -> TypedSubroutine {
+        let typed_body = self.fold_subroutine_body(&heading, body, st);
+        TypedSubroutine::Procedure(
+            heading.address,
+            heading.parameters,
+            typed_body,
+        )
+    }
+
     fn validate_return_for_function(
         &mut self,
         node_type: &NodeType,
@@ -207,7 +532,7 @@ impl TypeFolder {
         match statement {
             TypedStatement::Return(_t, maybe_value) => {
                 if let Some(value) = maybe_value {
-                    if value.node_type == node_type.clone() {
+                    if value_type(&value.node_type) == value_type(node_type) {
                         return true;
                     }
                 }
@@ -225,18 +550,31 @@ impl TypeFolder {
                     }
                 }
             }
-            TypedStatement::While(_c, body) => {
+            TypedStatement::While(_, body)
+            | TypedStatement::For(_, _, _, _, _, body) => {
                 if self.validate_return_for_function(node_type, body.as_ref()) {
                     return true;
                 }
             }
-            TypedStatement::Block(body) => {
+            TypedStatement::Block(body) | TypedStatement::Repeat(body, _) => {
                 for s in body {
                     if self.validate_return_for_function(node_type, s) {
                         return true;
                     }
                 }
             }
+            TypedStatement::Case(_selector, arms, else_body) => {
+                for (_labels, body) in arms {
+                    if self.validate_return_for_function(node_type, body) {
+                        return true;
+                    }
+                }
+                if let Some(body) = else_body {
+                    if self.validate_return_for_function(node_type, body) {
+                        return true;
+                    }
+                }
+            }
             _ => (),
         }
         false
@@ -244,33 +582,82 @@ impl TypeFolder {
 
     fn get_node_type_for_parameters(
         &mut self,
-        parameters: &Vec<(TypedVariable, TypedTypeDescription)>,
-    ) -> Vec<NodeType> {
+        parameters: &Vec<(TypedVariable, TypedTypeDescription, ParameterMode)>,
+    ) -> Vec<ParameterType> {
         let mut node_types = Vec::new();
         for param in parameters {
-            let (var, _desc) = param;
-            node_types.push(var.node_type.clone());
+            let (var, desc, mode) = param;
+            node_types.push(ParameterType {
+                node_type: var.node_type.clone(),
+                mode: *mode,
+                open_array: matches!(
+                    desc,
+                    TypedTypeDescription::Simple(NodeType::ArrayOf(..))
+                ),
+                lengths: match desc {
+                    TypedTypeDescription::Array(_, sizes) => {
+                        sizes.iter().map(constant_length).collect()
+                    }
+                    TypedTypeDescription::Simple(_) => Vec::new(),
+                },
+            });
         }
         node_types
     }
 
     fn fold_parameters(
         &mut self,
-        params: &Vec<(Token, TypeDescription)>,
+        params: &Vec<(Token, TypeDescription, ParameterMode)>,
         st: &mut Symboltable,
-    ) -> Option<Vec<(TypedVariable, TypedTypeDescription)>> {
+    ) -> Option<Vec<(TypedVariable, TypedTypeDescription, ParameterMode)>> {
         let mut typed_params = Vec::new();
         for param in params {
-            let (token, description) = param;
-            if let Some(declaration_core) =
-                self.fold_typed_declaration_core(token, description, st)
-            {
-                typed_params.push(declaration_core);
+            let (token, description, mode) = param;
+            let typed_param = match description {
+                TypeDescription::OpenArray(_array_token, element) => {
+                    self.fold_open_array_parameter(token, element, st)
+                }
+                _ => self.fold_typed_declaration_core(
+                    token,
+                    description,
+                    mode,
+                    st,
+                ),
+            };
+            if let Some((var, typed_description)) = typed_param {
+                typed_params.push((var, typed_description, *mode));
             }
         }
         Some(typed_params)
     }
 
+    // The sizes of an open array come from the caller, so its description
+    // has none.
+    fn fold_open_array_parameter(
+        &mut self,
+        name_token: &Token,
+        element: &TypeDescription,
+        st: &mut Symboltable,
+    ) -> Option<(TypedVariable, TypedTypeDescription)> {
+        if st.in_current_scope(&name_token.lexeme) {
+            self.handle_error(name_token, "Variable declared twice");
+            return None;
+        }
+        if let TypeDescription::Simple(type_token) = element {
+            if !self.is_valid_type(type_token, st) {
+                return None;
+            }
+        }
+        let element_type = match self.fold_type_description(element, st)? {
+            TypedTypeDescription::Simple(node_type) => node_type,
+            TypedTypeDescription::Array(node_type, _sizes) => node_type,
+        };
+        let node_type = NodeType::ArrayOf(Box::from(element_type), None);
+        let (variable, _description) =
+            self.declare_array(name_token, node_type.clone(), Vec::new(), st);
+        Some((variable, TypedTypeDescription::Simple(node_type)))
+    }
+
     fn fold_block(
         &mut self,
         node: &Vec<Statement>,
@@ -297,9 +684,24 @@ impl TypeFolder {
             Statement::Declaration(token, type_description) => {
                 self.fold_declaration(token, type_description, st)
             }
+            Statement::Constant(token, value) => {
+                self.fold_constant(token, value, st)
+            }
+            Statement::TypeDeclaration(token, type_description) => {
+                self.fold_type_declaration(token, type_description, st)
+            }
             Statement::While(condition, body) => {
                 self.fold_while(condition, body, st)
             }
+            Statement::Repeat(body, condition) => {
+                self.fold_repeat(body, condition, st)
+            }
+            Statement::Case(selector, arms, else_body) => {
+                self.fold_case(selector, arms, else_body, st)
+            }
+            Statement::For(variable, start, direction, limit, body) => {
+                self.fold_for(variable, start, direction, limit, body, st)
+            }
             Statement::If(condition, body, maybe_else_body) => {
                 if let Some(else_body) = maybe_else_body {
                     self.fold_if(condition, body, Some(else_body), st)
@@ -308,6 +710,12 @@ impl TypeFolder {
                 }
             }
             Statement::Block(block) => Some(self.fold_block(block, st)),
+            Statement::Subroutines(subroutines) => {
+                let mut typed_subroutines =
+                    self.fold_subroutines(subroutines, st);
+                self.nested_subroutines.append(&mut typed_subroutines);
+                None
+            }
             Statement::Assert(token, expression) => {
                 self.fold_assert(token, expression, st)
             }
@@ -327,8 +735,15 @@ impl TypeFolder {
         st: &mut Symboltable,
     ) -> Option<TypedStatement> {
         if let Some(target) = self.fold_variable(variable, st) {
+            self.check_loop_variable(&target);
             if let Some(value) = self.fold_expression(value, st) {
+                let value = self.widen(value, &target.node_type);
+                let value = self.narrow(value, &target.node_type);
                 if &target.node_type == &value.node_type {
+                    let lengths = self.variable_lengths(&target, st);
+                    if !self.check_lengths(&lengths, &value, st) {
+                        return None;
+                    }
                     return Some(TypedStatement::Assign(target, value));
                 } else {
                     self.handle_error(
@@ -349,9 +764,12 @@ impl TypeFolder {
     ) -> Option<TypedStatement> {
         let name = token.lexeme.clone();
         if !st.in_current_scope(&name) {
-            if let Some(declaration_core) =
-                self.fold_typed_declaration_core(token, type_description, st)
-            {
+            if let Some(declaration_core) = self.fold_typed_declaration_core(
+                token,
+                type_description,
+                &ParameterMode::Value,
+                st,
+            ) {
                 let (typed_var, description) = declaration_core;
                 Some(TypedStatement::Declaration(typed_var, description))
             } else {
@@ -363,31 +781,61 @@ impl TypeFolder {
         }
     }
 
-    fn simple_type_from_node_type(
+    // Constants are not declared in the generated code, their uses are
+    // folded into literals instead.
+    fn fold_constant(
         &mut self,
-        node_type: &NodeType,
-    ) -> SimpleType {
-        match node_type {
-            NodeType::Simple(t) => t.clone(),
-            NodeType::ArrayOf(t) => t.clone(),
+        token: &Token,
+        value: &Expression,
+        st: &mut Symboltable,
+    ) -> Option<TypedStatement> {
+        if st.in_current_scope(&token.lexeme) {
+            self.handle_error(token, "Constant declared twice");
+            return None;
+        }
+        let error_count = self.errors.len();
+        let typed_value = self.fold_expression(value, st)?;
+        if let TypedExpressionStructure::Literal = typed_value.substructure {
+            let address = self.get_new_simple_address();
+            st.add_entry(Entry {
+                name: token.lexeme.clone(),
+                category: ConstructCategory::Constant,
+                value: typed_value.token.lexeme.clone(),
+                entry_type: typed_value.node_type.clone(),
+                scope_number: st.get_current_scope_number(),
+                address,
+            });
+        } else if self.errors.len() == error_count {
+            self.handle_error(
+                token,
+                "Constant value must be computable at compile time",
+            );
         }
+        None
     }
 
     fn fold_typed_declaration_core(
         &mut self,
         name_token: &Token,
         type_description: &TypeDescription,
+        mode: &ParameterMode,
         st: &mut Symboltable,
     ) -> Option<(TypedVariable, TypedTypeDescription)> {
         let name = name_token.lexeme.clone();
         if !st.in_current_scope(&name) {
             match type_description {
-                TypeDescription::Simple(t) => {
-                    self.fold_typed_declaration_simple_core(name_token, t, st)
-                }
-                TypeDescription::Array(t, e) => {
-                    self.fold_typed_declaration_array_core(name_token, t, e, st)
-                }
+                TypeDescription::Simple(t) => self
+                    .fold_typed_declaration_simple_core(
+                        name_token, t, mode, st,
+                    ),
+                _ => match self.fold_type_description(type_description, st)? {
+                    TypedTypeDescription::Simple(node_type) => Some(
+                        self.declare_simple(name_token, node_type, mode, st),
+                    ),
+                    TypedTypeDescription::Array(node_type, sizes) => Some(
+                        self.declare_array(name_token, node_type, sizes, st),
+                    ),
+                },
             }
         } else {
             self.handle_error(name_token, "Variable declared twice");
@@ -399,112 +847,146 @@ impl TypeFolder {
         &mut self,
         name_token: &Token,
         type_token: &Token,
+        mode: &ParameterMode,
         st: &mut Symboltable,
     ) -> Option<(TypedVariable, TypedTypeDescription)> {
-        let type_identifier = type_token.lexeme.clone();
-        if let Some(entry_base_type) = type_id_to_simple_type(&type_identifier)
-        {
-            let entry_type = NodeType::Simple(entry_base_type);
-            let name = name_token.lexeme.clone();
-            if self.is_valid_type(type_token, st) {
-                let address = self.get_new_simple_address();
-                let entry = Entry {
-                    name,
-                    category: ConstructCategory::SimpleVar,
-                    scope_number: st.get_current_scope_number(),
-                    entry_type: entry_type.clone(),
-                    address: address.clone(),
-                    value: String::new(),
-                };
-                st.add_entry(entry);
-                Some((
-                    TypedVariable {
-                        token: name_token.clone(),
-                        address,
-                        node_type: entry_type.clone(),
-                        substructure: TypedVariableStructure::Simple,
-                    },
-                    TypedTypeDescription::Simple(entry_type.clone()),
-                ))
-            } else {
-                self.handle_error(type_token, "Usage of an undeclared type");
-                None
-            }
-        } else {
-            self.handle_error(type_token, "Usage of an unimplented type");
-            None
+        if !self.is_valid_type(type_token, st) {
+            return None;
         }
+        let entry_type =
+            match self.fold_simple_type_description(type_token, st)? {
+                TypedTypeDescription::Simple(entry_type) => entry_type,
+                TypedTypeDescription::Array(node_type, sizes) => {
+                    return Some(
+                        self.declare_array(name_token, node_type, sizes, st),
+                    )
+                }
+            };
+        Some(self.declare_simple(name_token, entry_type, mode, st))
     }
 
-    fn fold_typed_declaration_array_core(
+    fn declare_simple(
         &mut self,
         name_token: &Token,
-        type_token: &Token,
-        type_expression: &Expression,
+        entry_type: NodeType,
+        mode: &ParameterMode,
         st: &mut Symboltable,
-    ) -> Option<(TypedVariable, TypedTypeDescription)> {
-        if let Some(typed_expression) =
-            self.fold_expression(&type_expression, st)
+    ) -> (TypedVariable, TypedTypeDescription) {
+        let name = name_token.lexeme.clone();
+        let address = match mode {
+            ParameterMode::Var => self.get_new_reference_address(),
+            ParameterMode::Value => self.get_new_simple_address(),
+        };
+        let entry = Entry {
+            name,
+            category: ConstructCategory::SimpleVar,
+            scope_number: st.get_current_scope_number(),
+            entry_type: entry_type.clone(),
+            address: address.clone(),
+            value: String::new(),
+        };
+        st.add_entry(entry);
+        (
+            TypedVariable {
+                token: name_token.clone(),
+                address,
+                node_type: entry_type.clone(),
+                substructure: TypedVariableStructure::Simple,
+            },
+            TypedTypeDescription::Simple(entry_type.clone()),
+        )
+    }
+
+    fn declare_array(
+        &mut self,
+        name_token: &Token,
+        node_type: NodeType,
+        sizes: Vec<TypedExpression>,
+        st: &mut Symboltable,
+    ) -> (TypedVariable, TypedTypeDescription) {
+        let address = self.get_new_simple_address();
+        // The value keeps the sizes that are constants, separated by commas.
+        let lengths: Vec<String> = sizes
+            .iter()
+            .map(|size| match constant_length(size) {
+                Some(length) => length.to_string(),
+                None => String::new(),
+            })
+            .collect();
+        st.add_entry(Entry {
+            name: name_token.lexeme.clone(),
+            category: ConstructCategory::ArrayVar,
+            entry_type: node_type.clone(),
+            scope_number: st.get_current_scope_number(),
+            value: lengths.join(","),
+            address: address.clone(),
+        });
+        (
+            TypedVariable {
+                node_type: node_type.clone(),
+                token: name_token.clone(),
+                address,
+                substructure: TypedVariableStructure::Simple,
+            },
+            TypedTypeDescription::Array(node_type, sizes),
+        )
+    }
+
+    // A type declaration creates a TypeId entry. A declared array type gets
+    // the declared name and keeps its sizes in the entry's value, separated
+    // by commas. An alias of an existing type is the same type.
+    fn fold_type_declaration(
+        &mut self,
+        token: &Token,
+        type_description: &TypeDescription,
+        st: &mut Symboltable,
+    ) -> Option<TypedStatement> {
+        if st.in_current_scope(&token.lexeme) {
+            self.handle_error(token, "Type declared twice");
+            return None;
+        }
+        if let TypeDescription::Simple(type_token) = type_description {
+            if !self.is_valid_type(type_token, st) {
+                return None;
+            }
+        }
+        let (entry_type, value) = match self
+            .fold_type_description(type_description, st)?
         {
-            if typed_expression.node_type
-                == NodeType::Simple(SimpleType::Integer)
-            {
-                if let Some(type_entry) = st.lookup(&type_token.lexeme) {
-                    if type_entry.category == ConstructCategory::TypeId {
-                        if st.in_current_scope(&name_token.lexeme) {
-                            self.handle_error(
-                                name_token,
-                                "Variable declared twice",
-                            )
-                        } else {
-                            let address = self.get_new_simple_address();
-                            let node_type = NodeType::ArrayOf(
-                                self.simple_type_from_node_type(
-                                    &type_entry.entry_type,
-                                ),
-                            );
-                            let new_entry = Entry {
-                                name: name_token.lexeme.clone(),
-                                category: ConstructCategory::ArrayVar,
-                                entry_type: node_type.clone(),
-                                scope_number: st.get_current_scope_number(),
-                                value: String::new(),
-                                address: address.clone(),
-                            };
-                            st.add_entry(new_entry);
-                            let type_description = TypedTypeDescription::Array(
-                                node_type.clone(),
-                                typed_expression,
-                            );
-                            return Some((
-                                TypedVariable {
-                                    node_type: node_type.clone(),
-                                    token: name_token.clone(),
-                                    address,
-                                    substructure:
-                                        TypedVariableStructure::Simple,
-                                },
-                                type_description,
-                            ));
-                        }
+            TypedTypeDescription::Simple(node_type) => {
+                (node_type, String::new())
+            }
+            TypedTypeDescription::Array(node_type, sizes) => {
+                let mut lexemes = Vec::new();
+                for size in sizes {
+                    if let TypedExpressionStructure::Literal = size.substructure
+                    {
+                        lexemes.push(size.token.lexeme);
                     } else {
-                        let msg =
-                            format!("{} is not a type", type_token.lexeme);
-                        self.handle_error(type_token, msg.as_str());
+                        self.handle_error(
+                            &size.token,
+                            "Array type size must be computable at compile time",
+                        );
+                        return None;
                     }
-                } else {
-                    self.handle_error(
-                        type_token,
-                        "Usage of an undeclared type",
-                    );
                 }
-            } else {
-                self.handle_error(
-                    &typed_expression.token,
-                    "Array size must be of type integer",
-                );
+                let node_type = match node_type {
+                    NodeType::ArrayOf(t, None) => {
+                        NodeType::ArrayOf(t, Some(token.lexeme.clone()))
+                    }
+                    named => named,
+                };
+                (node_type, lexemes.join(","))
             }
-        }
+        };
+        st.add_entry(Entry {
+            name: token.lexeme.clone(),
+            category: ConstructCategory::TypeId,
+            value,
+            entry_type,
+            scope_number: st.get_current_scope_number(),
+            address: Address::new_simple(0),
+        });
         None
     }
 
@@ -517,58 +999,211 @@ impl TypeFolder {
             TypeDescription::Simple(token) => {
                 self.fold_simple_type_description(token, st)
             }
-            TypeDescription::Array(token, expression) => {
-                self.fold_array_type_description(token, expression, st)
+            TypeDescription::Array(element, size) => {
+                self.fold_array_type_description(element, size, st)
             }
-        }
-    }
-
-    fn fold_simple_type_description(
-        &mut self,
-        name: &Token,
+            TypeDescription::OpenArray(token, _element) => {
+                self.handle_error(
+                    token,
+                    "Arrays without a size can only be parameters",
+                );
+                None
+            }
+            TypeDescription::Record(_token, fields) => {
+                self.fold_record_type_description(fields, st)
+            }
+            TypeDescription::Enumeration(_token, members) => {
+                self.fold_enumeration_type_description(members, st)
+            }
+            TypeDescription::Subrange(token, low, high) => {
+                self.fold_subrange_type_description(token, low, high, st)
+            }
+        }
+    }
+
+    fn fold_subrange_type_description(
+        &mut self,
+        token: &Token,
+        low: &Expression,
+        high: &Expression,
         st: &mut Symboltable,
     ) -> Option<TypedTypeDescription> {
-        if let Some(entry) = st.lookup(&name.lexeme) {
-            if let ConstructCategory::TypeId = entry.category {
-                let core_type =
-                    self.simple_type_from_node_type(&entry.entry_type);
-                return Some(TypedTypeDescription::Simple(NodeType::Simple(
-                    core_type,
-                )));
+        let mut bounds = Vec::new();
+        for bound in [low, high] {
+            let typed_bound = self.fold_expression(bound, st)?;
+            match (&typed_bound.node_type, &typed_bound.substructure) {
+                (
+                    NodeType::Simple(SimpleType::Integer),
+                    TypedExpressionStructure::Literal,
+                ) => match typed_bound.token.lexeme.parse() {
+                    Ok(value) => bounds.push(value),
+                    Err(_) => {
+                        self.handle_error(
+                            &typed_bound.token,
+                            "Subrange bound out of range",
+                        );
+                        return None;
+                    }
+                },
+                _ => {
+                    self.handle_error(
+                        &typed_bound.token,
+                        "Subrange bounds must be Integer constants",
+                    );
+                    return None;
+                }
             }
         }
-        None
+        if bounds[0] > bounds[1] {
+            self.handle_error(token, "Empty subrange");
+            return None;
+        }
+        Some(TypedTypeDescription::Simple(NodeType::Subrange(
+            bounds[0], bounds[1],
+        )))
     }
 
-    fn fold_array_type_description(
+    // The members become constants whose values are their ordinals.
+    fn fold_enumeration_type_description(
+        &mut self,
+        members: &Vec<Token>,
+        st: &mut Symboltable,
+    ) -> Option<TypedTypeDescription> {
+        let names: Vec<String> =
+            members.iter().map(|member| member.lexeme.clone()).collect();
+        let enumeration =
+            NodeType::Enumeration(self.type_definitions.len() as u64, names);
+        for (ordinal, member) in members.iter().enumerate() {
+            if st.in_current_scope(&member.lexeme) {
+                self.handle_error(member, "Enumeration member declared twice");
+                return None;
+            }
+            let address = self.get_new_simple_address();
+            st.add_entry(Entry {
+                name: member.lexeme.clone(),
+                category: ConstructCategory::Constant,
+                value: ordinal.to_string(),
+                entry_type: enumeration.clone(),
+                scope_number: st.get_current_scope_number(),
+                address,
+            });
+        }
+        self.type_definitions.push(enumeration.clone());
+        Some(TypedTypeDescription::Simple(enumeration))
+    }
+
+    fn fold_record_type_description(
+        &mut self,
+        fields: &Vec<(Token, TypeDescription)>,
+        st: &mut Symboltable,
+    ) -> Option<TypedTypeDescription> {
+        let mut typed_fields: Vec<(String, NodeType, Vec<i64>)> = Vec::new();
+        for (name, field_type) in fields {
+            if typed_fields.iter().any(|(field, ..)| field == &name.lexeme) {
+                self.handle_error(name, "Field declared twice");
+                return None;
+            }
+            if let TypeDescription::Simple(type_token) = field_type {
+                if !self.is_valid_type(type_token, st) {
+                    return None;
+                }
+            }
+            match self.fold_type_description(field_type, st)? {
+                TypedTypeDescription::Simple(node_type) => typed_fields.push((
+                    name.lexeme.clone(),
+                    node_type,
+                    Vec::new(),
+                )),
+                // Every record has the same arrays, so their sizes are
+                // constants.
+                TypedTypeDescription::Array(node_type, sizes) => {
+                    let mut lengths = Vec::new();
+                    for size in sizes {
+                        match (&size.substructure, size.token.lexeme.parse()) {
+                            (TypedExpressionStructure::Literal, Ok(length)) => {
+                                lengths.push(length)
+                            }
+                            _ => {
+                                self.handle_error(
+                                    &size.token,
+                                    "Record field size must be computable at compile time",
+                                );
+                                return None;
+                            }
+                        }
+                    }
+                    typed_fields.push((name.lexeme.clone(), node_type, lengths))
+                }
+            }
+        }
+        let record =
+            NodeType::Record(self.type_definitions.len() as u64, typed_fields);
+        self.type_definitions.push(record.clone());
+        Some(TypedTypeDescription::Simple(record))
+    }
+
+    fn fold_simple_type_description(
         &mut self,
         name: &Token,
-        expression: &Expression,
         st: &mut Symboltable,
     ) -> Option<TypedTypeDescription> {
         if let Some(entry) = st.lookup(&name.lexeme) {
             if let ConstructCategory::TypeId = entry.category {
-                let core_type =
-                    self.simple_type_from_node_type(&entry.entry_type);
-                if let Some(typed_expression) =
-                    self.fold_expression(expression, st)
-                {
-                    if let NodeType::Simple(SimpleType::Integer) =
-                        typed_expression.node_type
-                    {
-                        return Some(TypedTypeDescription::Array(
-                            NodeType::ArrayOf(core_type),
-                            typed_expression,
-                        ));
-                    } else {
-                        self.handle_error(name, "Array size must be integer");
-                    }
+                let node_type = entry.entry_type.clone();
+                if let NodeType::ArrayOf(..) = node_type {
+                    let sizes = entry
+                        .value
+                        .split(',')
+                        .map(|size| {
+                            self.constant_literal(
+                                name,
+                                String::from(size),
+                                NodeType::Simple(SimpleType::Integer),
+                            )
+                        })
+                        .collect();
+                    return Some(TypedTypeDescription::Array(node_type, sizes));
                 }
+                return Some(TypedTypeDescription::Simple(node_type));
             }
         }
         None
     }
 
+    // The element type can be an array itself, the sizes of its dimensions
+    // come after the size of this one.
+    fn fold_array_type_description(
+        &mut self,
+        element: &TypeDescription,
+        size: &Expression,
+        st: &mut Symboltable,
+    ) -> Option<TypedTypeDescription> {
+        if let TypeDescription::Simple(type_token) = element {
+            if !self.is_valid_type(type_token, st) {
+                return None;
+            }
+        }
+        let typed_size = self.fold_expression(size, st)?;
+        if typed_size.node_type != NodeType::Simple(SimpleType::Integer) {
+            self.handle_error(
+                &typed_size.token,
+                "Array size must be of type integer",
+            );
+            return None;
+        }
+        let (element_type, mut sizes) = match self
+            .fold_type_description(element, st)?
+        {
+            TypedTypeDescription::Simple(node_type) => (node_type, Vec::new()),
+            TypedTypeDescription::Array(node_type, sizes) => (node_type, sizes),
+        };
+        sizes.insert(0, typed_size);
+        Some(TypedTypeDescription::Array(
+            NodeType::ArrayOf(Box::from(element_type), None),
+            sizes,
+        ))
+    }
+
     fn is_valid_type(
         &mut self,
         type_token: &Token,
@@ -656,6 +1291,235 @@ impl TypeFolder {
         None
     }
 
+    fn fold_repeat(
+        &mut self,
+        body: &Vec<Statement>,
+        condition: &Expression,
+        st: &mut Symboltable,
+    ) -> Option<TypedStatement> {
+        if let TypedStatement::Block(typed_body) = self.fold_block(body, st) {
+            if let Some(typed_condition) = self.fold_expression(condition, st) {
+                if typed_condition.node_type
+                    == NodeType::Simple(SimpleType::Boolean)
+                {
+                    return Some(TypedStatement::Repeat(
+                        typed_body,
+                        typed_condition,
+                    ));
+                } else {
+                    self.handle_error(
+                        &typed_condition.token,
+                        "Until condition must be of Boolean type",
+                    );
+                }
+            }
+        }
+        None
+    }
+
+    fn fold_case(
+        &mut self,
+        selector: &Expression,
+        arms: &Vec<(Vec<CaseLabel>, Statement)>,
+        else_body: &Option<Box<Statement>>,
+        st: &mut Symboltable,
+    ) -> Option<TypedStatement> {
+        let typed_selector = self.fold_expression(selector, st)?;
+        match &typed_selector.node_type {
+            NodeType::Simple(SimpleType::Integer)
+            | NodeType::Simple(SimpleType::Char)
+            | NodeType::Simple(SimpleType::String)
+            | NodeType::Enumeration(..) => (),
+            _ => {
+                self.handle_error(
+                    &typed_selector.token,
+                    "Case selector must be of Integer, Char, String or enumeration type",
+                );
+                return None;
+            }
+        }
+        let mut seen_labels: Vec<TypedCaseLabel> = Vec::new();
+        let mut typed_arms = Vec::new();
+        for (labels, body) in arms {
+            let mut typed_labels = Vec::new();
+            for label in labels {
+                let selector_type = &typed_selector.node_type;
+                if let Some((typed_label, token)) =
+                    self.fold_case_label(label, selector_type, st)
+                {
+                    if seen_labels
+                        .iter()
+                        .any(|seen| case_labels_overlap(seen, &typed_label))
+                    {
+                        self.handle_error(&token, "Duplicate case label");
+                    }
+                    seen_labels.push(typed_label.clone());
+                    typed_labels.push(typed_label);
+                }
+            }
+            if let Some(typed_body) = self.fold_statement(body, st) {
+                typed_arms.push((typed_labels, typed_body));
+            }
+        }
+        let typed_else_body = match else_body {
+            Some(body) => Some(Box::from(self.fold_statement(body, st)?)),
+            None => None,
+        };
+        Some(TypedStatement::Case(
+            typed_selector,
+            typed_arms,
+            typed_else_body,
+        ))
+    }
+
+    fn fold_case_label(
+        &mut self,
+        label: &CaseLabel,
+        selector_type: &NodeType,
+        st: &mut Symboltable,
+    ) -> Option<(TypedCaseLabel, Token)> {
+        match label {
+            CaseLabel::Single(expression) => {
+                let constant =
+                    self.fold_case_constant(expression, selector_type, st)?;
+                if let NodeType::Simple(SimpleType::String) = selector_type {
+                    let lexeme = constant.token.lexeme.clone();
+                    return Some((
+                        TypedCaseLabel::String(lexeme),
+                        constant.token,
+                    ));
+                }
+                let value = self.case_constant_value(&constant)?;
+                Some((TypedCaseLabel::Range(value, value), constant.token))
+            }
+            CaseLabel::Range(low, high) => {
+                let typed_low =
+                    self.fold_case_constant(low, selector_type, st)?;
+                let typed_high =
+                    self.fold_case_constant(high, selector_type, st)?;
+                if let NodeType::Simple(SimpleType::String) = selector_type {
+                    self.handle_error(
+                        &typed_low.token,
+                        "Case label ranges are for integers only",
+                    );
+                    return None;
+                }
+                let low_value = self.case_constant_value(&typed_low)?;
+                let high_value = self.case_constant_value(&typed_high)?;
+                if low_value > high_value {
+                    self.handle_error(
+                        &typed_low.token,
+                        "Empty case label range",
+                    );
+                    return None;
+                }
+                Some((
+                    TypedCaseLabel::Range(low_value, high_value),
+                    typed_low.token,
+                ))
+            }
+        }
+    }
+
+    fn fold_case_constant(
+        &mut self,
+        expression: &Expression,
+        selector_type: &NodeType,
+        st: &mut Symboltable,
+    ) -> Option<TypedExpression> {
+        let constant = self.fold_expression(expression, st)?;
+        if let TypedExpressionStructure::Literal = constant.substructure {
+            if &constant.node_type == selector_type {
+                return Some(constant);
+            }
+            self.handle_error(
+                &constant.token,
+                "Case label type does not match the selector",
+            );
+        } else {
+            self.handle_error(&constant.token, "Case label must be a constant");
+        }
+        None
+    }
+
+    fn case_constant_value(
+        &mut self,
+        constant: &TypedExpression,
+    ) -> Option<i64> {
+        let value = match constant.node_type {
+            NodeType::Simple(SimpleType::Char) => {
+                char_code(&constant.token.lexeme)
+            }
+            _ => constant.token.lexeme.parse::<i64>().ok(),
+        };
+        match value {
+            Some(value) => Some(value),
+            None => {
+                self.handle_error(
+                    &constant.token,
+                    "Case label is out of range",
+                );
+                None
+            }
+        }
+    }
+
+    fn fold_for(
+        &mut self,
+        variable: &Token,
+        start: &Expression,
+        direction: &Token,
+        limit: &Expression,
+        body: &Statement,
+        st: &mut Symboltable,
+    ) -> Option<TypedStatement> {
+        let typed_variable = self.fold_simple_variable(variable, st)?;
+        let typed_start = self.fold_expression(start, st)?;
+        let typed_limit = self.fold_expression(limit, st)?;
+        let control_type = typed_variable.node_type.clone();
+        if value_type(&control_type) != NodeType::Simple(SimpleType::Integer) {
+            self.handle_error(
+                variable,
+                "For loop control variable must be of Integer type",
+            );
+            return None;
+        }
+        // The values in between are in range when the bounds are.
+        let typed_start = self.narrow(typed_start, &control_type);
+        let typed_limit = self.narrow(typed_limit, &control_type);
+        if typed_start.node_type != control_type
+            || typed_limit.node_type != control_type
+        {
+            self.handle_error(
+                direction,
+                "For loop bounds must be of Integer type",
+            );
+            return None;
+        }
+        self.check_loop_variable(&typed_variable);
+        self.loop_variables.push(typed_variable.address.clone());
+        let typed_body = self.fold_statement(body, st);
+        self.loop_variables.pop();
+        Some(TypedStatement::For(
+            typed_variable,
+            Box::from(typed_start),
+            direction.clone(),
+            Box::from(typed_limit),
+            self.get_new_simple_address(),
+            Box::from(typed_body?),
+        ))
+    }
+
+    // The control variable of a for loop can not be changed in its body.
+    fn check_loop_variable(&mut self, variable: &TypedVariable) {
+        if self.loop_variables.contains(&variable.address) {
+            self.handle_error(
+                &variable.token,
+                "Assignment to a for loop control variable",
+            );
+        }
+    }
+
     fn fold_assert(
         &mut self,
         token: &Token,
@@ -694,14 +1558,14 @@ impl TypeFolder {
                 self.handle_error(token, "Failed to check argument");
             }
         }
-        if let Some(entry) = st.lookup(&token.lexeme) {
+        if let Some(entry) = st.lookup_subroutine(&token.lexeme) {
             match &entry.category {
                 ConstructCategory::Special => {
                     self.fold_special_call_expression(token, entry, typedargs)
                 }
                 ConstructCategory::Function(parameters, out_type) => self
                     .fold_regular_call_expression(
-                        token, &entry, parameters, out_type, typedargs,
+                        token, &entry, parameters, out_type, typedargs, st,
                     ),
                 _ => {
                     self.handle_error(
@@ -724,20 +1588,29 @@ impl TypeFolder {
         &mut self,
         token: &Token,
         entry: &Entry,
-        arguments: Vec<TypedExpression>,
+        mut arguments: Vec<TypedExpression>,
     ) -> Option<TypedExpression> {
         let ok_special = match entry.name.as_str() {
-            "size" => {
-                if let Some(argument) = arguments.get(0) {
-                    if let NodeType::ArrayOf(_t) = &argument.node_type {
-                        Some(argument)
-                    } else {
-                        None
+            "ord" => return self.fold_ord(token, arguments),
+            "chr" => return self.fold_chr(token, arguments),
+            "succ" => return self.fold_step(token, arguments, 1),
+            "pred" => return self.fold_step(token, arguments, -1),
+            "length" | "copy" | "pos" | "upcase" | "lowercase" | "trim" => {
+                return self.fold_string_function(token, &entry.name, arguments)
+            }
+            "abs" | "sqr" | "sqrt" | "sin" | "cos" | "exp" | "ln" | "trunc"
+            | "round" => {
+                return self.fold_math_function(token, &entry.name, arguments)
+            }
+            "size" => match arguments.pop() {
+                Some(argument) if arguments.is_empty() => {
+                    match argument.node_type {
+                        NodeType::ArrayOf(..) => Some(argument),
+                        _ => None,
                     }
-                } else {
-                    None
                 }
-            }
+                _ => None,
+            },
             _ => None,
         };
         if let Some(arg) = ok_special {
@@ -745,28 +1618,378 @@ impl TypeFolder {
                 token: token.clone(),
                 address: self.get_new_simple_address(),
                 node_type: NodeType::Simple(SimpleType::Integer),
-                substructure: TypedExpressionStructure::Size(
-                    arg.address.clone(),
-                ),
+                substructure: TypedExpressionStructure::Size(Box::from(arg)),
             })
         } else {
             None
         }
     }
 
+    // Upcase and lowercase work on chars as well as on strings.
+    fn fold_string_function(
+        &mut self,
+        token: &Token,
+        name: &str,
+        arguments: Vec<TypedExpression>,
+    ) -> Option<TypedExpression> {
+        let string = NodeType::Simple(SimpleType::String);
+        let integer = NodeType::Simple(SimpleType::Integer);
+        let char_type = NodeType::Simple(SimpleType::Char);
+        let is_char =
+            arguments.len() == 1 && arguments[0].node_type == char_type;
+        let (function, parameters, out_type) = match (name, is_char) {
+            ("length", _) => ("strlen", vec![string], integer),
+            ("copy", _) => (
+                "mp_copy",
+                vec![string.clone(), integer.clone(), integer],
+                string,
+            ),
+            ("pos", _) => ("mp_pos", vec![string.clone(), string], integer),
+            ("upcase", true) => ("toupper", vec![char_type.clone()], char_type),
+            ("upcase", false) => ("mp_upcase", vec![string.clone()], string),
+            ("lowercase", true) => {
+                ("tolower", vec![char_type.clone()], char_type)
+            }
+            ("lowercase", false) => {
+                ("mp_lowercase", vec![string.clone()], string)
+            }
+            ("trim", _) => ("mp_trim", vec![string.clone()], string),
+            _ => return None,
+        };
+        self.fold_runtime_call(
+            token,
+            function,
+            arguments,
+            &parameters,
+            out_type,
+        )
+    }
+
+    // Abs and sqr keep the type of their argument, the rest take reals and
+    // integers are widened to them.
+    fn fold_math_function(
+        &mut self,
+        token: &Token,
+        name: &str,
+        arguments: Vec<TypedExpression>,
+    ) -> Option<TypedExpression> {
+        let integer = NodeType::Simple(SimpleType::Integer);
+        let real = NodeType::Simple(SimpleType::Real);
+        let is_integer = arguments.len() == 1
+            && value_type(&arguments[0].node_type) == integer;
+        let (function, parameter, out_type) = match (name, is_integer) {
+            ("abs", true) => ("abs", integer.clone(), integer),
+            ("abs", false) => ("fabs", real.clone(), real),
+            ("sqr", true) => ("mp_sqr_integer", integer.clone(), integer),
+            ("sqr", false) => ("mp_sqr_real", real.clone(), real),
+            ("sqrt", _) => ("sqrt", real.clone(), real),
+            ("sin", _) => ("sin", real.clone(), real),
+            ("cos", _) => ("cos", real.clone(), real),
+            ("exp", _) => ("exp", real.clone(), real),
+            ("ln", _) => ("log", real.clone(), real),
+            ("trunc", _) => ("trunc", real, integer),
+            ("round", _) => ("round", real, integer),
+            _ => return None,
+        };
+        self.fold_runtime_call(
+            token,
+            function,
+            arguments,
+            &vec![parameter],
+            out_type,
+        )
+    }
+
+    // The string argument of insert and delete is a var-parameter, the call
+    // assigns the changed string to it.
+    fn fold_string_procedure(
+        &mut self,
+        token: &Token,
+        name: &str,
+        arguments: Vec<TypedExpression>,
+    ) -> Option<TypedStatement> {
+        let string = NodeType::Simple(SimpleType::String);
+        let integer = NodeType::Simple(SimpleType::Integer);
+        let (function, parameters, target) = match name {
+            "insert" => (
+                "mp_insert",
+                vec![string.clone(), string.clone(), integer],
+                1,
+            ),
+            _ => (
+                "mp_delete",
+                vec![string.clone(), integer.clone(), integer],
+                0,
+            ),
+        };
+        let variable = match arguments.get(target).map(|arg| &arg.substructure)
+        {
+            Some(TypedExpressionStructure::Variable(variable)) => {
+                Some((**variable).clone())
+            }
+            _ => None,
+        };
+        let value = self.fold_runtime_call(
+            token,
+            function,
+            arguments,
+            &parameters,
+            string,
+        )?;
+        match variable {
+            Some(variable) => {
+                self.check_loop_variable(&variable);
+                Some(TypedStatement::Assign(variable, value))
+            }
+            None => {
+                self.handle_error(
+                    token,
+                    "Only variables can be passed to var-parameters",
+                );
+                None
+            }
+        }
+    }
+
+    // Runtime functions returning strings get the line for their overflow
+    // errors as the last argument.
+    fn fold_runtime_call(
+        &mut self,
+        token: &Token,
+        function: &str,
+        arguments: Vec<TypedExpression>,
+        parameters: &Vec<NodeType>,
+        out_type: NodeType,
+    ) -> Option<TypedExpression> {
+        if arguments.len() != parameters.len() {
+            self.handle_error(
+                token,
+                format!(
+                    "{} takes {} arguments",
+                    token.lexeme,
+                    parameters.len()
+                )
+                .as_str(),
+            );
+            return None;
+        }
+        let mut typed_arguments = Vec::new();
+        for (argument, parameter) in arguments.into_iter().zip(parameters) {
+            let argument = self.widen(argument, parameter);
+            if &value_type(&argument.node_type) != parameter {
+                self.handle_error(
+                    &argument.token,
+                    "Argument type does not match parameters",
+                );
+                return None;
+            }
+            typed_arguments.push(argument);
+        }
+        if out_type == NodeType::Simple(SimpleType::String) {
+            let line = (token.row + 1).to_string();
+            typed_arguments.push(self.constant_literal(
+                token,
+                line,
+                NodeType::Simple(SimpleType::Integer),
+            ));
+        }
+        Some(TypedExpression {
+            token: token.clone(),
+            address: self.get_new_simple_address(),
+            node_type: out_type,
+            substructure: TypedExpressionStructure::RuntimeCall(
+                String::from(function),
+                typed_arguments,
+            ),
+        })
+    }
+
+    fn fold_ord(
+        &mut self,
+        token: &Token,
+        mut arguments: Vec<TypedExpression>,
+    ) -> Option<TypedExpression> {
+        let integer = NodeType::Simple(SimpleType::Integer);
+        let argument = match arguments.pop() {
+            Some(argument) if arguments.is_empty() => argument,
+            _ => {
+                self.handle_error(token, "ord takes exactly one argument");
+                return None;
+            }
+        };
+        match (&argument.node_type, &argument.substructure) {
+            (NodeType::Simple(SimpleType::Integer), _) => Some(argument),
+            (NodeType::Enumeration(..), TypedExpressionStructure::Literal) => {
+                Some(TypedExpression {
+                    node_type: integer,
+                    ..argument
+                })
+            }
+            (
+                NodeType::Simple(SimpleType::Char),
+                TypedExpressionStructure::Literal,
+            ) => {
+                let code = char_code(&argument.token.lexeme)?;
+                Some(self.constant_literal(
+                    &argument.token,
+                    code.to_string(),
+                    integer,
+                ))
+            }
+            (NodeType::Enumeration(..), _)
+            | (NodeType::Simple(SimpleType::Char), _) => {
+                Some(TypedExpression {
+                    token: token.clone(),
+                    address: self.get_new_simple_address(),
+                    node_type: integer,
+                    substructure: TypedExpressionStructure::Conversion(
+                        Box::from(argument),
+                    ),
+                })
+            }
+            _ => {
+                self.handle_error(
+                    &argument.token,
+                    "ord needs an Integer, Char or enumeration argument",
+                );
+                None
+            }
+        }
+    }
+
+    // The code of a constant is checked here, other codes are range checked
+    // at run time unless compiled with --no-range-checks.
+    fn fold_chr(
+        &mut self,
+        token: &Token,
+        mut arguments: Vec<TypedExpression>,
+    ) -> Option<TypedExpression> {
+        let char_type = NodeType::Simple(SimpleType::Char);
+        let argument = match arguments.pop() {
+            Some(argument) if arguments.is_empty() => argument,
+            _ => {
+                self.handle_error(token, "chr takes exactly one argument");
+                return None;
+            }
+        };
+        match (&argument.node_type, &argument.substructure) {
+            (
+                NodeType::Simple(SimpleType::Integer),
+                TypedExpressionStructure::Literal,
+            ) => match argument.token.lexeme.parse::<i64>() {
+                Ok(code) if (0..=255).contains(&code) => {
+                    Some(self.constant_literal(
+                        &argument.token,
+                        char_literal(code),
+                        char_type,
+                    ))
+                }
+                _ => {
+                    self.handle_error(&argument.token, "Value out of range");
+                    None
+                }
+            },
+            (NodeType::Simple(SimpleType::Integer), _) => {
+                Some(TypedExpression {
+                    token: token.clone(),
+                    address: self.get_new_simple_address(),
+                    node_type: char_type,
+                    substructure: TypedExpressionStructure::Conversion(
+                        Box::from(argument),
+                    ),
+                })
+            }
+            _ => {
+                self.handle_error(
+                    &argument.token,
+                    "chr needs an Integer argument",
+                );
+                None
+            }
+        }
+    }
+
+    // Stepping a constant is folded, so stepping past the ends of an
+    // enumeration is caught here. Otherwise it is checked at run time.
+    fn fold_step(
+        &mut self,
+        token: &Token,
+        mut arguments: Vec<TypedExpression>,
+        step: i32,
+    ) -> Option<TypedExpression> {
+        let argument = match arguments.pop() {
+            Some(argument) if arguments.is_empty() => argument,
+            _ => {
+                self.handle_error(
+                    token,
+                    format!("{} takes exactly one argument", token.lexeme)
+                        .as_str(),
+                );
+                return None;
+            }
+        };
+        let (first, last) = match &argument.node_type {
+            NodeType::Simple(SimpleType::Integer) => (i32::MIN, i32::MAX),
+            NodeType::Enumeration(_number, members) => {
+                (0, members.len() as i32 - 1)
+            }
+            _ => {
+                self.handle_error(
+                    &argument.token,
+                    format!(
+                        "{} needs an Integer or enumeration argument",
+                        token.lexeme
+                    )
+                    .as_str(),
+                );
+                return None;
+            }
+        };
+        if let TypedExpressionStructure::Literal = argument.substructure {
+            let ordinal: i32 = argument.token.lexeme.parse().ok()?;
+            return match ordinal.checked_add(step) {
+                Some(value) if first <= value && value <= last => {
+                    Some(self.constant_literal(
+                        token,
+                        value.to_string(),
+                        argument.node_type,
+                    ))
+                }
+                _ => {
+                    self.handle_error(token, "Value out of range");
+                    None
+                }
+            };
+        }
+        Some(TypedExpression {
+            token: token.clone(),
+            address: self.get_new_simple_address(),
+            node_type: argument.node_type.clone(),
+            substructure: TypedExpressionStructure::Step(
+                Box::from(argument),
+                step,
+            ),
+        })
+    }
+
     fn fold_regular_call_expression(
         &mut self,
         token: &Token,
         entry: &Entry,
-        parameters: &Vec<NodeType>,
+        parameters: &Vec<ParameterType>,
         out_type: &NodeType,
         arguments: Vec<TypedExpression>,
+        st: &Symboltable,
     ) -> Option<TypedExpression> {
-        if self.match_params_to_arguments(parameters, &arguments) {
+        if let Some(mut arguments) =
+            self.match_params_to_arguments(token, parameters, arguments, st)
+        {
+            arguments
+                .append(&mut self.environment_arguments(token, &entry.address));
             Some(TypedExpression {
                 token: token.clone(),
                 address: self.get_new_simple_address(),
-                node_type: out_type.clone(),
+                node_type: value_type(out_type),
                 substructure: TypedExpressionStructure::Call(
                     entry.address.clone(),
                     arguments,
@@ -779,23 +2002,125 @@ impl TypeFolder {
 
     fn match_params_to_arguments(
         &mut self,
-        parameters: &Vec<NodeType>,
-        arguments: &Vec<TypedExpression>,
+        token: &Token,
+        parameters: &Vec<ParameterType>,
+        arguments: Vec<TypedExpression>,
+        st: &Symboltable,
+    ) -> Option<Vec<TypedExpression>> {
+        if parameters.len() != arguments.len() {
+            self.handle_error(
+                token,
+                "Arguments lenght does not match parameters",
+            );
+            return None;
+        }
+        let mut matched_arguments = Vec::new();
+        for (param, arg) in parameters.iter().zip(arguments) {
+            let param_type = &param.node_type;
+            if param.open_array {
+                matched_arguments
+                    .push(self.fold_open_array_argument(param, arg)?);
+                continue;
+            }
+            let arg = match param.mode {
+                ParameterMode::Value => {
+                    let arg = self.widen(arg, param_type);
+                    self.narrow(arg, param_type)
+                }
+                ParameterMode::Var => self.fold_var_argument(arg)?,
+            };
+            if param_type.clone() != arg.node_type.clone() {
+                self.handle_error(
+                    &arg.token,
+                    "Argument type does not match parameters",
+                );
+                return None;
+            }
+            if !self.check_lengths(&param.lengths, &arg, st) {
+                return None;
+            }
+            matched_arguments.push(arg);
+        }
+        Some(matched_arguments)
+    }
+
+    // An open array takes any array of its element type.
+    fn fold_open_array_argument(
+        &mut self,
+        param: &ParameterType,
+        arg: TypedExpression,
+    ) -> Option<TypedExpression> {
+        let matches = match (&param.node_type, &arg.node_type) {
+            (
+                NodeType::ArrayOf(element, _),
+                NodeType::ArrayOf(arg_element, _),
+            ) => element == arg_element,
+            _ => false,
+        };
+        if !matches {
+            self.handle_error(
+                &arg.token,
+                "Argument type does not match parameters",
+            );
+            return None;
+        }
+        match param.mode {
+            ParameterMode::Value => Some(arg),
+            ParameterMode::Var => self.fold_var_argument(arg),
+        }
+    }
+
+    // The lengths of an array variable that are known at compile time,
+    // outermost first. Indexing drops the outer dimensions.
+    fn variable_lengths(
+        &self,
+        variable: &TypedVariable,
+        st: &Symboltable,
+    ) -> Vec<Option<i64>> {
+        let indices = match &variable.substructure {
+            TypedVariableStructure::Indexed(indices) => indices.len(),
+            _ => 0,
+        };
+        match st.lookup(&variable.token.lexeme) {
+            Some(entry) if entry.category == ConstructCategory::ArrayVar => {
+                entry
+                    .value
+                    .split(',')
+                    .skip(indices)
+                    .map(|length| length.parse().ok())
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // Arrays of different lengths can not be copied into each other. When
+    // both lengths are known the mismatch is reported here, the rest are
+    // checked at run time.
+    fn check_lengths(
+        &mut self,
+        lengths: &Vec<Option<i64>>,
+        value: &TypedExpression,
+        st: &Symboltable,
     ) -> bool {
-        for i in 0..parameters.len() {
-            if let Some(param) = parameters.get(i) {
-                if let Some(arg) = arguments.get(i) {
-                    if param.clone() != arg.node_type.clone() {
-                        self.handle_error(
-                            &arg.token,
-                            "Argument type does not match parameters",
-                        );
-                        return false;
-                    }
-                } else {
-                    self.errors.push(format!(
-                        "Arguments lenght does not match parameters"
-                    ));
+        let value_lengths = match &value.substructure {
+            TypedExpressionStructure::Variable(variable)
+            | TypedExpressionStructure::Reference(variable) => {
+                self.variable_lengths(variable, st)
+            }
+            _ => Vec::new(),
+        };
+        for (length, value_length) in lengths.iter().zip(value_lengths) {
+            if let (Some(length), Some(value_length)) = (length, value_length) {
+                if *length != value_length {
+                    self.handle_error(
+                        &value.token,
+                        format!(
+                            "Array sizes do not match, {} is not {}",
+                            value_length, length
+                        )
+                        .as_str(),
+                    );
                     return false;
                 }
             }
@@ -803,12 +2128,57 @@ impl TypeFolder {
         true
     }
 
+    // Only variables have an address that can be handed to a var-parameter.
+    fn fold_var_argument(
+        &mut self,
+        arg: TypedExpression,
+    ) -> Option<TypedExpression> {
+        let is_array = matches!(arg.node_type, NodeType::ArrayOf(..));
+        match arg.substructure {
+            // Arrays are passed as pointers anyway.
+            TypedExpressionStructure::Variable(variable) if is_array => {
+                Some(TypedExpression {
+                    token: arg.token,
+                    address: arg.address,
+                    node_type: arg.node_type,
+                    substructure: TypedExpressionStructure::Variable(variable),
+                })
+            }
+            // The reference has the type of the variable, a subrange is not
+            // compatible with the Integers it holds.
+            TypedExpressionStructure::Variable(variable) => {
+                self.check_loop_variable(&variable);
+                Some(TypedExpression {
+                    token: arg.token,
+                    address: arg.address,
+                    node_type: variable.node_type.clone(),
+                    substructure: TypedExpressionStructure::Reference(variable),
+                })
+            }
+            _ => {
+                self.handle_error(
+                    &arg.token,
+                    "Only variables can be passed to var-parameters",
+                );
+                None
+            }
+        }
+    }
+
     fn fold_call_statement(
         &mut self,
         token: &Token,
         arguments: &Vec<Expression>,
         st: &mut Symboltable,
     ) -> Option<TypedStatement> {
+        if let Some(entry) = st.lookup_subroutine(&token.lexeme) {
+            if entry.category == ConstructCategory::Special
+                && (entry.name == "write" || entry.name == "writeln")
+            {
+                let newline = entry.name == "writeln";
+                return self.fold_write(token, arguments, newline, st);
+            }
+        }
         let mut typedargs = Vec::new();
         for arg in arguments {
             if let Some(typedarg) = self.fold_expression(arg, st) {
@@ -817,14 +2187,13 @@ impl TypeFolder {
                 self.handle_error(&token, "Failed to check argument");
             }
         }
-        if let Some(entry) = st.lookup(&token.lexeme) {
+        if let Some(entry) = st.lookup_subroutine(&token.lexeme) {
             match &entry.category {
                 ConstructCategory::Special => {
                     self.fold_special_call_stmnt(token, entry, typedargs)
                 }
-                ConstructCategory::Procedure(parameters) => {
-                    self.fold_regular_call(token, entry, parameters, typedargs)
-                }
+                ConstructCategory::Procedure(parameters) => self
+                    .fold_regular_call(token, entry, parameters, typedargs, st),
                 ConstructCategory::Function(_args, _params) => {
                     self.handle_error(
                         token,
@@ -845,17 +2214,90 @@ impl TypeFolder {
 
     fn fold_special_call_stmnt(
         &mut self,
-        _token: &Token,
+        token: &Token,
         entry: &Entry,
         arguments: Vec<TypedExpression>,
     ) -> Option<TypedStatement> {
         match entry.name.as_str() {
             "read" => Some(TypedStatement::Read(self.read_args(arguments))),
-            "writeln" => Some(TypedStatement::Write(arguments)),
+            "insert" | "delete" => {
+                self.fold_string_procedure(token, &entry.name, arguments)
+            }
             _ => None,
         }
     }
 
+    fn fold_write(
+        &mut self,
+        token: &Token,
+        arguments: &Vec<Expression>,
+        newline: bool,
+        st: &mut Symboltable,
+    ) -> Option<TypedStatement> {
+        let mut typedargs = Vec::new();
+        for arg in arguments {
+            if let Some(typedarg) = self.fold_write_argument(arg, st) {
+                typedargs.push(typedarg)
+            } else {
+                self.handle_error(token, "Failed to check argument");
+            }
+        }
+        Some(TypedStatement::Write(typedargs, newline))
+    }
+
+    // Arrays are written element by element, so the field width and the
+    // precision concern their elements. Only reals have a precision.
+    fn fold_write_argument(
+        &mut self,
+        arg: &Expression,
+        st: &mut Symboltable,
+    ) -> Option<TypedWriteArgument> {
+        let (value, width, precision) = match arg {
+            Expression::Formatted(value, _colon, width, precision) => {
+                (value.as_ref(), Some(width), precision.as_ref())
+            }
+            _ => (arg, None, None),
+        };
+        let value = self.fold_expression(value, st)?;
+        let element_type = written_type(&value.node_type).clone();
+        if let NodeType::Record(..) = element_type {
+            self.handle_error(&value.token, "Records can not be written");
+            return None;
+        }
+        let width = match width {
+            Some(width) => Some(self.fold_field_width(width, st)?),
+            None => None,
+        };
+        let precision = match precision {
+            Some(_) if element_type != NodeType::Simple(SimpleType::Real) => {
+                self.handle_error(&value.token, "Only reals have a precision");
+                return None;
+            }
+            Some(precision) => Some(self.fold_field_width(precision, st)?),
+            None => None,
+        };
+        Some(TypedWriteArgument {
+            value,
+            width,
+            precision,
+        })
+    }
+
+    fn fold_field_width(
+        &mut self,
+        width: &Expression,
+        st: &mut Symboltable,
+    ) -> Option<TypedExpression> {
+        let width = self.fold_expression(width, st)?;
+        if value_type(&width.node_type) == NodeType::Simple(SimpleType::Integer)
+        {
+            Some(width)
+        } else {
+            self.handle_error(&width.token, "Field width must be an integer");
+            None
+        }
+    }
+
     fn read_args(
         &mut self,
         arguments: Vec<TypedExpression>,
@@ -864,6 +2306,14 @@ impl TypeFolder {
         for arg in arguments {
             match arg.substructure {
                 TypedExpressionStructure::Variable(typed_var) => {
+                    if let NodeType::Enumeration(..) = typed_var.node_type {
+                        self.handle_error(
+                            &arg.token,
+                            "Enumeration values can not be read",
+                        );
+                        continue;
+                    }
+                    self.check_loop_variable(&typed_var);
                     vars.push(typed_var);
                 }
                 _ => self
@@ -875,12 +2325,17 @@ impl TypeFolder {
 
     fn fold_regular_call(
         &mut self,
-        _token: &Token,
+        token: &Token,
         entry: &Entry,
-        parameters: &Vec<NodeType>,
+        parameters: &Vec<ParameterType>,
         arguments: Vec<TypedExpression>,
+        st: &Symboltable,
     ) -> Option<TypedStatement> {
-        if self.match_params_to_arguments(parameters, &arguments) {
+        if let Some(mut arguments) =
+            self.match_params_to_arguments(token, parameters, arguments, st)
+        {
+            arguments
+                .append(&mut self.environment_arguments(token, &entry.address));
             Some(TypedStatement::Call(entry.address.clone(), arguments))
         } else {
             None
@@ -895,6 +2350,14 @@ impl TypeFolder {
     ) -> Option<TypedStatement> {
         if let Some(val) = value {
             if let Some(expr) = self.fold_expression(val, st) {
+                // Subrange results are checked like assignments.
+                let expr = match self.result_types.last() {
+                    Some(Some(result_type)) => {
+                        let result_type = result_type.clone();
+                        self.narrow(expr, &result_type)
+                    }
+                    _ => expr,
+                };
                 Some(TypedStatement::Return(token.clone(), Some(expr)))
             } else {
                 None
@@ -921,6 +2384,16 @@ impl TypeFolder {
             Expression::Call(id, parameters) => {
                 self.fold_call_expression(id, parameters, st)
             }
+            Expression::Index(array, id, indices) => {
+                self.fold_index_expression(array, id, indices, st)
+            }
+            Expression::Formatted(_value, colon, _width, _precision) => {
+                self.handle_error(
+                    colon,
+                    "Field widths are only allowed in write and writeln",
+                );
+                None
+            }
         }
     }
 
@@ -928,6 +2401,7 @@ impl TypeFolder {
         let maybe_node_type = match token.token_kind {
             TokenKind::IntegerLiteral => Some(SimpleType::Integer),
             TokenKind::StringLiteral => Some(SimpleType::String),
+            TokenKind::CharLiteral => Some(SimpleType::Char),
             TokenKind::RealLiteral => Some(SimpleType::Real),
             _ => {
                 self.handle_error(token, "Non literal parsed as one");
@@ -946,16 +2420,63 @@ impl TypeFolder {
         }
     }
 
+    // A literal built by the compiler, the token is kept for error positions.
+    fn constant_literal(
+        &mut self,
+        token: &Token,
+        value: String,
+        node_type: NodeType,
+    ) -> TypedExpression {
+        let token_kind = match node_type {
+            NodeType::Simple(SimpleType::Integer)
+            | NodeType::Enumeration(..) => TokenKind::IntegerLiteral,
+            NodeType::Simple(SimpleType::Real) => TokenKind::RealLiteral,
+            NodeType::Simple(SimpleType::String) => TokenKind::StringLiteral,
+            NodeType::Simple(SimpleType::Char) => TokenKind::CharLiteral,
+            _ => TokenKind::Identifier,
+        };
+        TypedExpression {
+            token: Token {
+                token_kind,
+                lexeme: value,
+                ..token.clone()
+            },
+            address: self.get_new_simple_address(),
+            node_type,
+            substructure: TypedExpressionStructure::Literal,
+        }
+    }
+
     fn fold_variable_as_expression(
         &mut self,
         var: &Variable,
         st: &mut Symboltable,
     ) -> Option<TypedExpression> {
+        if let Variable::Simple(token) = var {
+            if let Some(entry) = st.lookup(&token.lexeme) {
+                if let ConstructCategory::Constant = entry.category {
+                    let node_type = entry.entry_type.clone();
+                    let value = entry.value.clone();
+                    return Some(
+                        self.constant_literal(token, value, node_type),
+                    );
+                }
+            }
+        }
+        if let Variable::Field(record, field) = var {
+            let record = Expression::Variable(record.clone());
+            let typed_record = self.fold_expression(&record, st)?;
+            if !matches!(typed_record.node_type, NodeType::Record(..)) {
+                // Selecting from anything but a record is a call, "a.size"
+                // is "size(a)".
+                return self.fold_call_expression(field, &vec![record], st);
+            }
+        }
         if let Some(variable) = self.fold_variable(var, st) {
             Some(TypedExpression {
                 token: variable.token.clone(),
                 address: variable.address.clone(),
-                node_type: variable.node_type.clone(),
+                node_type: value_type(&variable.node_type),
                 substructure: TypedExpressionStructure::Variable(Box::from(
                     variable,
                 )),
@@ -973,7 +2494,93 @@ impl TypeFolder {
         match var {
             Variable::Simple(t) => self.fold_simple_variable(t, st),
             Variable::Indexed(t, e) => self.fold_indexed_variable(t, e, st),
+            Variable::Field(record, field) => {
+                let typed_record = self.fold_variable(record, st)?;
+                self.fold_field(typed_record, field)
+            }
+            Variable::FieldIndexed(array, indices) => {
+                self.fold_field_indexed_variable(array, indices, st)
+            }
+        }
+    }
+
+    // The selected field is an array or a string, the last index can select
+    // a character of a string.
+    fn fold_field_indexed_variable(
+        &mut self,
+        array: &Variable,
+        indices: &Vec<Expression>,
+        st: &mut Symboltable,
+    ) -> Option<TypedVariable> {
+        let mut variable = self.fold_variable(array, st)?;
+        let mut typed_indices = Vec::new();
+        for index in indices {
+            typed_indices.push(self.fold_expression(index, st)?);
+        }
+        let last = typed_indices.len() - 1;
+        for (i, index) in typed_indices.into_iter().enumerate() {
+            if index.node_type != NodeType::Simple(SimpleType::Integer) {
+                self.handle_error(
+                    &variable.token,
+                    "Array index must be integer",
+                );
+                return None;
+            }
+            variable = match variable.node_type.clone() {
+                NodeType::ArrayOf(element, _) => TypedVariable {
+                    token: variable.token.clone(),
+                    address: self.get_new_indexed_address(
+                        variable.address.clone(),
+                        index.address.clone(),
+                    ),
+                    node_type: *element,
+                    substructure: TypedVariableStructure::Element(
+                        Box::from(variable),
+                        Box::from(index),
+                    ),
+                },
+                NodeType::Simple(SimpleType::String) if i == last => {
+                    self.string_character(variable, index)
+                }
+                _ => {
+                    self.handle_error(
+                        &variable.token,
+                        "Too many indices for array",
+                    );
+                    return None;
+                }
+            };
+        }
+        Some(variable)
+    }
+
+    fn fold_field(
+        &mut self,
+        record: TypedVariable,
+        field: &Token,
+    ) -> Option<TypedVariable> {
+        if let NodeType::Record(_number, fields) = &record.node_type {
+            if let Some((_name, node_type, _sizes)) =
+                fields.iter().find(|(name, ..)| name == &field.lexeme)
+            {
+                return Some(TypedVariable {
+                    token: field.clone(),
+                    address: Address::new_field(
+                        record.address.clone(),
+                        &field.lexeme,
+                    ),
+                    node_type: node_type.clone(),
+                    substructure: TypedVariableStructure::Field(Box::from(
+                        record,
+                    )),
+                });
+            }
+            let msg = format!("Record has no field {}", field.lexeme);
+            self.handle_error(field, msg.as_str());
+        } else {
+            self.handle_error(field, "Field selection from a non-record");
         }
+        None
     }
 
     fn fold_simple_variable(
@@ -982,9 +2589,13 @@ impl TypeFolder {
         st: &Symboltable,
     ) -> Option<TypedVariable> {
         if let Some(entry) = st.lookup(&token.lexeme.clone()) {
+            if let ConstructCategory::Constant = entry.category {
+                self.handle_error(token, "Assignment to a constant");
+                return None;
+            }
             Some(TypedVariable {
                 token: token.clone(),
-                address: entry.address.clone(),
+                address: self.frame_address(&entry.address),
                 node_type: entry.entry_type.clone(),
                 substructure: TypedVariableStructure::Simple,
             })
@@ -997,43 +2608,133 @@ impl TypeFolder {
         }
     }
 
+    // Every index selects an element of one more dimension, so indexing
+    // with fewer indices than there are dimensions gives an array.
     fn fold_indexed_variable(
         &mut self,
         token: &Token,
-        expression: &Expression,
+        indices: &Vec<Expression>,
         st: &mut Symboltable,
     ) -> Option<TypedVariable> {
-        if let Some(typed_expression) = self.fold_expression(expression, st) {
-            if let Some(entry) = st.lookup(&token.lexeme) {
-                if let ConstructCategory::ArrayVar = entry.category {
-                    let node_type = NodeType::Simple(node_type_to_simple_type(
-                        &entry.entry_type,
-                    ));
-                    if NodeType::Simple(SimpleType::Integer)
-                        == typed_expression.node_type.clone()
-                    {
-                        return Some(TypedVariable {
-                            token: token.clone(),
-                            address: self.get_new_indexed_address(
-                                entry.address.clone(),
-                                typed_expression.address.clone(),
-                            ),
-                            node_type,
-                            substructure: TypedVariableStructure::Indexed(
-                                typed_expression,
-                            ),
-                        });
-                    } else {
-                        self.handle_error(token, "Array index must be integer");
-                    }
-                } else {
-                    self.handle_error(token, "Indexing of simple variable");
-                }
-            } else {
+        let mut typed_indices = Vec::new();
+        for index in indices {
+            typed_indices.push(self.fold_expression(index, st)?);
+        }
+        let entry = match st.lookup(&token.lexeme) {
+            Some(entry) => entry,
+            None => {
                 self.handle_error(token, "Usage of undeclared variable");
+                return None;
             }
+        };
+        let string = NodeType::Simple(SimpleType::String);
+        let is_string = entry.category == ConstructCategory::SimpleVar
+            && entry.entry_type == string;
+        if entry.category != ConstructCategory::ArrayVar && !is_string {
+            self.handle_error(token, "Indexing of simple variable");
+            return None;
+        }
+        let mut address = self.frame_address(&entry.address);
+        let mut node_type = entry.entry_type.clone();
+        for (i, index) in typed_indices.iter().enumerate() {
+            if index.node_type != NodeType::Simple(SimpleType::Integer) {
+                self.handle_error(token, "Array index must be integer");
+                return None;
+            }
+            node_type = match node_type {
+                NodeType::ArrayOf(element, _) => *element,
+                // The last index can select a character of a string.
+                NodeType::Simple(SimpleType::String)
+                    if i == typed_indices.len() - 1 =>
+                {
+                    let index = typed_indices.pop()?;
+                    let substructure = match typed_indices.is_empty() {
+                        true => TypedVariableStructure::Simple,
+                        false => TypedVariableStructure::Indexed(typed_indices),
+                    };
+                    let string = TypedVariable {
+                        token: token.clone(),
+                        address,
+                        node_type: string,
+                        substructure,
+                    };
+                    return Some(self.string_character(string, index));
+                }
+                _ => {
+                    self.handle_error(token, "Too many indices for array");
+                    return None;
+                }
+            };
+            address =
+                self.get_new_indexed_address(address, index.address.clone());
+        }
+        Some(TypedVariable {
+            token: token.clone(),
+            address,
+            node_type,
+            substructure: TypedVariableStructure::Indexed(typed_indices),
+        })
+    }
+
+    fn fold_index_expression(
+        &mut self,
+        array: &Expression,
+        id: &Token,
+        indices: &Vec<Expression>,
+        st: &mut Symboltable,
+    ) -> Option<TypedExpression> {
+        let typed_array = self.fold_expression(array, st)?;
+        let mut typed_indices = Vec::new();
+        for index in indices {
+            typed_indices.push(self.fold_expression(index, st)?);
+        }
+        let mut address = typed_array.address.clone();
+        let mut node_type = typed_array.node_type.clone();
+        for index in &typed_indices {
+            if index.node_type != NodeType::Simple(SimpleType::Integer) {
+                self.handle_error(id, "Array index must be integer");
+                return None;
+            }
+            node_type = match node_type {
+                NodeType::ArrayOf(element, _) => *element,
+                _ => {
+                    self.handle_error(id, "Too many indices for array");
+                    return None;
+                }
+            };
+            address =
+                self.get_new_indexed_address(address, index.address.clone());
+        }
+        Some(TypedExpression {
+            token: id.clone(),
+            address,
+            node_type,
+            substructure: TypedExpressionStructure::Indexed(
+                Box::from(typed_array),
+                typed_indices,
+            ),
+        })
+    }
+
+    fn string_character(
+        &mut self,
+        string: TypedVariable,
+        index: TypedExpression,
+    ) -> TypedVariable {
+        let offset = self.get_new_simple_address();
+        TypedVariable {
+            token: string.token.clone(),
+            address: self.get_new_indexed_address(
+                string.address.clone(),
+                offset.clone(),
+            ),
+            node_type: NodeType::Simple(SimpleType::Char),
+            substructure: TypedVariableStructure::Character(
+                Box::from(string),
+                Box::from(index),
+                offset,
+            ),
         }
-        None
     }
 
     fn fold_binary_expression(
@@ -1045,9 +2746,24 @@ impl TypeFolder {
     ) -> Option<TypedExpression> {
         if let Some(typed_lhs) = self.fold_expression(&lhs, st) {
             if let Some(typed_rhs) = self.fold_expression(&rhs, st) {
+                let rhs_type = typed_rhs.node_type.clone();
+                let typed_lhs = self.widen(typed_lhs, &rhs_type);
+                let typed_rhs = self.widen(typed_rhs, &typed_lhs.node_type);
                 if &typed_lhs.node_type == &typed_rhs.node_type {
                     let maybe_node_type = match &typed_lhs.node_type {
-                        NodeType::ArrayOf(_st) => None,
+                        NodeType::ArrayOf(..) | NodeType::Record(..) => {
+                            self.handle_error(
+                                op,
+                                "Bad operator for arrays and records",
+                            );
+                            None
+                        }
+                        NodeType::Enumeration(..) => {
+                            self.type_enumeration_expression(op)
+                        }
+                        NodeType::Subrange(..) => {
+                            self.type_integer_expression(op)
+                        }
                         NodeType::Simple(simple_type) => match simple_type {
                             SimpleType::Integer => {
                                 self.type_integer_expression(op)
@@ -1058,10 +2774,18 @@ impl TypeFolder {
                             SimpleType::String => {
                                 self.type_string_expression(op)
                             }
+                            SimpleType::Char => self.type_char_expression(op),
                             SimpleType::Real => self.type_real_expression(op),
                         },
                     };
                     if let Some(node_type) = maybe_node_type {
+                        if let Some(value) =
+                            self.evaluate_binary(op, &typed_lhs, &typed_rhs)
+                        {
+                            return Some(
+                                self.constant_literal(op, value, node_type),
+                            );
+                        }
                         if let Some(op_kind) =
                             string_as_opkind(&op.lexeme)
                         {
@@ -1078,12 +2802,236 @@ impl TypeFolder {
                             });
                         }
                     }
+                } else {
+                    self.handle_error(op, "Mismatched types in expression");
                 }
             }
         }
         None
     }
 
+    // Operations on two literals are evaluated at compile time, which is
+    // what makes constant expressions constants. The integers follow the
+    // 32 bit int of the generated code. None leaves the operation for the
+    // runtime.
+    fn evaluate_binary(
+        &mut self,
+        op: &Token,
+        lhs: &TypedExpression,
+        rhs: &TypedExpression,
+    ) -> Option<String> {
+        match (&lhs.substructure, &rhs.substructure) {
+            (
+                TypedExpressionStructure::Literal,
+                TypedExpressionStructure::Literal,
+            ) => (),
+            _ => return None,
+        }
+        let op_kind = string_as_opkind(&op.lexeme)?;
+        let (a, b) = (&lhs.token.lexeme, &rhs.token.lexeme);
+        let simple_type = match &lhs.node_type {
+            NodeType::Simple(simple_type) => simple_type,
+            // Only the relational operators apply to enumerations, so the
+            // ordinals can be compared as integers.
+            NodeType::Enumeration(..) => &SimpleType::Integer,
+            _ => return None,
+        };
+        match simple_type {
+            SimpleType::Integer => {
+                let a: i32 = a.parse().ok()?;
+                let b: i32 = b.parse().ok()?;
+                let value = match op_kind {
+                    OpKind::Relational(relation) => {
+                        return Some(compare(a, b, &relation))
+                    }
+                    OpKind::Addition => a.checked_add(b),
+                    OpKind::NumArithmetic(NumArithmetic::Minus) => {
+                        a.checked_sub(b)
+                    }
+                    OpKind::NumArithmetic(NumArithmetic::Multi) => {
+                        a.checked_mul(b)
+                    }
+                    OpKind::NumArithmetic(NumArithmetic::Division)
+                    | OpKind::Modulo
+                        if b == 0 =>
+                    {
+                        self.handle_error(op, "Division by zero");
+                        return None;
+                    }
+                    OpKind::NumArithmetic(NumArithmetic::Division) => {
+                        a.checked_div(b)
+                    }
+                    OpKind::Modulo => a.checked_rem(b),
+                    OpKind::BoolArithmetic(_) => return None,
+                };
+                match value {
+                    Some(value) => Some(value.to_string()),
+                    None => {
+                        self.handle_error(op, "Integer overflow");
+                        None
+                    }
+                }
+            }
+            SimpleType::Real => {
+                let a: f64 = a.parse().ok()?;
+                let b: f64 = b.parse().ok()?;
+                let value = match op_kind {
+                    OpKind::Relational(relation) => {
+                        return Some(compare(a, b, &relation))
+                    }
+                    OpKind::Addition => a + b,
+                    OpKind::NumArithmetic(NumArithmetic::Minus) => a - b,
+                    OpKind::NumArithmetic(NumArithmetic::Multi) => a * b,
+                    OpKind::NumArithmetic(NumArithmetic::Division) => a / b,
+                    _ => return None,
+                };
+                if value.is_finite() {
+                    Some(format!("{:?}", value))
+                } else {
+                    self.handle_error(op, "Real overflow or division by zero");
+                    None
+                }
+            }
+            SimpleType::String => {
+                let a = &a[1..a.len() - 1];
+                let b = &b[1..b.len() - 1];
+                match op_kind {
+                    OpKind::Addition => Some(format!("\"{}{}\"", a, b)),
+                    OpKind::Relational(Relational::Equal) => {
+                        Some(compare(a, b, &Relational::Equal))
+                    }
+                    OpKind::Relational(Relational::NotEqual) => {
+                        Some(compare(a, b, &Relational::NotEqual))
+                    }
+                    _ => None,
+                }
+            }
+            SimpleType::Char => {
+                let a = char_code(a)?;
+                let b = char_code(b)?;
+                match op_kind {
+                    OpKind::Relational(relation) => {
+                        Some(compare(a, b, &relation))
+                    }
+                    _ => None,
+                }
+            }
+            SimpleType::Boolean => {
+                let a = a == "1";
+                let b = b == "1";
+                match op_kind {
+                    OpKind::Relational(relation) => {
+                        Some(compare(a, b, &relation))
+                    }
+                    OpKind::BoolArithmetic(BoolArithmetic::And) => {
+                        Some(boolean_value(a && b))
+                    }
+                    OpKind::BoolArithmetic(BoolArithmetic::Or) => {
+                        Some(boolean_value(a || b))
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    // Integers widen to reals and chars to strings, anything else is
+    // returned as it is and left for the caller to type check.
+    fn widen(
+        &mut self,
+        expression: TypedExpression,
+        target: &NodeType,
+    ) -> TypedExpression {
+        if expression.node_type == NodeType::Simple(SimpleType::Char)
+            && target == &NodeType::Simple(SimpleType::String)
+        {
+            return self.char_as_string(expression);
+        }
+        if expression.node_type == NodeType::Simple(SimpleType::Integer)
+            && target == &NodeType::Simple(SimpleType::Real)
+        {
+            if let TypedExpressionStructure::Literal = expression.substructure {
+                return TypedExpression {
+                    node_type: target.clone(),
+                    ..expression
+                };
+            }
+            TypedExpression {
+                token: expression.token.clone(),
+                address: self.get_new_simple_address(),
+                node_type: target.clone(),
+                substructure: TypedExpressionStructure::Conversion(Box::from(
+                    expression,
+                )),
+            }
+        } else {
+            expression
+        }
+    }
+
+    // A char literal becomes a string literal, so constant expressions
+    // like 'a' + "b" stay constants.
+    fn char_as_string(
+        &mut self,
+        expression: TypedExpression,
+    ) -> TypedExpression {
+        let string = NodeType::Simple(SimpleType::String);
+        if let TypedExpressionStructure::Literal = expression.substructure {
+            let lexeme = &expression.token.lexeme;
+            let character = match &lexeme[1..lexeme.len() - 1] {
+                "\"" => "\\\"",
+                character => character,
+            };
+            let value = format!("\"{}\"", character);
+            return self.constant_literal(&expression.token, value, string);
+        }
+        TypedExpression {
+            token: expression.token.clone(),
+            address: self.get_new_simple_address(),
+            node_type: string,
+            substructure: TypedExpressionStructure::RuntimeCall(
+                String::from("mp_char_string"),
+                vec![expression],
+            ),
+        }
+    }
+
+    // Values given to a subrange are checked against its bounds, at compile
+    // time when the value is a constant.
+    fn narrow(
+        &mut self,
+        expression: TypedExpression,
+        target: &NodeType,
+    ) -> TypedExpression {
+        let (low, high) = match target {
+            NodeType::Subrange(low, high) => (*low, *high),
+            _ => return expression,
+        };
+        if expression.node_type != NodeType::Simple(SimpleType::Integer) {
+            return expression;
+        }
+        if let TypedExpressionStructure::Literal = expression.substructure {
+            let value: i32 = expression.token.lexeme.parse().unwrap_or(low);
+            if value < low || value > high {
+                let msg =
+                    format!("Value {} is not in {}..{}", value, low, high);
+                self.handle_error(&expression.token, msg.as_str());
+            }
+            return TypedExpression {
+                node_type: target.clone(),
+                ..expression
+            };
+        }
+        TypedExpression {
+            token: expression.token.clone(),
+            address: expression.address.clone(),
+            node_type: target.clone(),
+            substructure: TypedExpressionStructure::RangeCheck(Box::from(
+                expression,
+            )),
+        }
+    }
+
     fn type_integer_expression(&mut self, op: &Token) -> Option<NodeType> {
         match string_as_opkind(&op.lexeme) {
             Some(op_kind) => match op_kind {
@@ -1105,6 +3053,32 @@ impl TypeFolder {
         }
     }
 
+    fn type_enumeration_expression(&mut self, op: &Token) -> Option<NodeType> {
+        match string_as_opkind(&op.lexeme) {
+            Some(OpKind::Relational(_relation)) => {
+                Some(NodeType::Simple(SimpleType::Boolean))
+            }
+            Some(_op_kind) => {
+                self.handle_error(op, "Bad operator for enumeration");
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn type_char_expression(&mut self, op: &Token) -> Option<NodeType> {
+        match string_as_opkind(&op.lexeme) {
+            Some(OpKind::Relational(_relation)) => {
+                Some(NodeType::Simple(SimpleType::Boolean))
+            }
+            Some(_op_kind) => {
+                self.handle_error(op, "Bad operator for char");
+                None
+            }
+            None => None,
+        }
+    }
+
     fn type_string_expression(&mut self, op: &Token) -> Option<NodeType> {
         match string_as_opkind(&op.lexeme) {
             Some(op_kind) => match op_kind {
@@ -1170,6 +3144,19 @@ impl TypeFolder {
                     if rhs_typed.node_type
                         == NodeType::Simple(SimpleType::Boolean)
                     {
+                        if let TypedExpressionStructure::Literal =
+                            rhs_typed.substructure
+                        {
+                            let value =
+                                boolean_value(rhs_typed.token.lexeme != "1");
+                            return Some(TypedExpression {
+                                token: Token {
+                                    lexeme: value,
+                                    ..rhs_typed.token.clone()
+                                },
+                                ..rhs_typed
+                            });
+                        }
                         Some(TypedExpression {
                             node_type: NodeType::Simple(SimpleType::Boolean),
                             address: self.get_new_simple_address(),
@@ -1187,7 +3174,67 @@ impl TypeFolder {
                     None
                 }
             }
+            TokenKind::Plus | TokenKind::Minus => {
+                let rhs_typed = self.fold_expression(rhs, st)?;
+                match rhs_typed.node_type {
+                    NodeType::Simple(SimpleType::Integer)
+                    | NodeType::Simple(SimpleType::Real) => {
+                        Some(self.fold_sign(op, rhs_typed))
+                    }
+                    _ => {
+                        self.handle_error(
+                            op,
+                            "Sign operator is for integers and reals only",
+                        );
+                        None
+                    }
+                }
+            }
             _ => None,
         }
     }
+
+    // A signed literal is folded into a literal, so that for example
+    // negative array sizes are plain declarations in the generated code.
+    fn fold_sign(
+        &mut self,
+        op: &Token,
+        rhs_typed: TypedExpression,
+    ) -> TypedExpression {
+        if let TypedExpressionStructure::Literal = rhs_typed.substructure {
+            let mut token = rhs_typed.token.clone();
+            if let TokenKind::Minus = op.token_kind {
+                token.lexeme = match rhs_typed.node_type {
+                    // The literal 2147483648 fits only when it is negated.
+                    NodeType::Simple(SimpleType::Integer) => {
+                        let value = token.lexeme.parse::<i64>().ok();
+                        match value
+                            .and_then(|value| value.checked_neg())
+                            .and_then(|value| i32::try_from(value).ok())
+                        {
+                            Some(value) => value.to_string(),
+                            None => {
+                                self.handle_error(op, "Integer overflow");
+                                token.lexeme
+                            }
+                        }
+                    }
+                    _ => match token.lexeme.strip_prefix('-') {
+                        Some(positive) => String::from(positive),
+                        None => format!("-{}", token.lexeme),
+                    },
+                };
+            }
+            TypedExpression { token, ..rhs_typed }
+        } else {
+            TypedExpression {
+                node_type: rhs_typed.node_type.clone(),
+                address: self.get_new_simple_address(),
+                token: op.clone(),
+                substructure: TypedExpressionStructure::Unary(Box::from(
+                    rhs_typed,
+                )),
+            }
+        }
+    }
 }
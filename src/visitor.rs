@@ -9,13 +9,13 @@ pub trait Visitor {
     fn visit_procedure(
         &mut self,
         name: &Token,
-        params: &Vec<(Token, TypeDescription)>,
+        params: &Vec<(Token, TypeDescription, ParameterMode)>,
         body: &Vec<Statement>,
     );
     fn visit_function(
         &mut self,
         name: &Token,
-        params: &Vec<(Token, TypeDescription)>,
+        params: &Vec<(Token, TypeDescription, ParameterMode)>,
         body: &Vec<Statement>,
         out_type: &TypeDescription,
     );
@@ -34,6 +34,21 @@ pub trait Visitor {
         else_body: Option<&Statement>,
     );
     fn visit_while(&mut self, condition: &Expression, body: &Statement);
+    fn visit_repeat(&mut self, body: &Vec<Statement>, condition: &Expression);
+    fn visit_case(
+        &mut self,
+        selector: &Expression,
+        arms: &Vec<(Vec<CaseLabel>, Statement)>,
+        else_body: &Option<Box<Statement>>,
+    );
+    fn visit_for(
+        &mut self,
+        variable: &Token,
+        start: &Expression,
+        direction: &Token,
+        limit: &Expression,
+        body: &Statement,
+    );
     fn visit_assert(&mut self, token: &Token, condition: &Expression);
     fn visit_call(&mut self, token: &Token, arguments: &Vec<Expression>);
     fn visit_return(&mut self, token: &Token, value: &Option<Expression>);
@@ -74,6 +89,26 @@ pub trait TypedVisitor {
         condition: &TypedExpression,
         body: &TypedStatement,
     );
+    fn visit_repeat(
+        &mut self,
+        body: &Vec<TypedStatement>,
+        condition: &TypedExpression,
+    );
+    fn visit_case(
+        &mut self,
+        selector: &TypedExpression,
+        arms: &Vec<(Vec<TypedCaseLabel>, TypedStatement)>,
+        else_body: &Option<Box<TypedStatement>>,
+    );
+    fn visit_for(
+        &mut self,
+        variable: &TypedVariable,
+        start: &TypedExpression,
+        direction: &Token,
+        limit: &TypedExpression,
+        limit_copy: &Address,
+        body: &TypedStatement,
+    );
     fn visit_assert(&mut self, token: &Token, condition: &TypedExpression);
     fn visit_call(
         &mut self,